@@ -0,0 +1,115 @@
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rocket::local::Client;
+use rocket::State;
+use rocket_jsonapi::links::ResourceRoutes;
+use rocket_jsonapi::response::{JsonApiCollection, JsonApiDataResponse};
+use rocket_jsonapi_derive::JsonApiResource;
+use serde::Serialize;
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+#[derive(Serialize, JsonApiResource)]
+#[jsonapi(type = "people")]
+struct Person {
+    id: i64,
+}
+
+#[derive(Serialize, JsonApiResource)]
+#[jsonapi(type = "articles", links(self = "/articles/{id}"))]
+struct Article {
+    id: i64,
+    title: String,
+    body: String,
+    #[serde(skip_serializing)]
+    #[jsonapi(relationship)]
+    author: Person,
+}
+
+// Comments link to their resource through the `ResourceRoutes` of the server
+#[derive(Serialize, JsonApiResource)]
+#[jsonapi(type = "comments")]
+struct Comment {
+    id: String,
+    text: String,
+    #[serde(skip_serializing)]
+    #[jsonapi(relationship)]
+    author: Person,
+}
+
+struct Articles(Vec<Article>);
+
+struct Comments(Vec<Comment>);
+
+#[get("/articles?<count>")]
+fn articles(
+    count: usize,
+    articles: State<Articles>,
+) -> JsonApiDataResponse<JsonApiCollection<&Article>> {
+    JsonApiDataResponse(Ok(JsonApiCollection::data(
+        articles.inner().0.iter().take(count).collect(),
+    )))
+}
+
+#[get("/comments?<count>")]
+fn comments(
+    count: usize,
+    comments: State<Comments>,
+) -> JsonApiDataResponse<JsonApiCollection<&Comment>> {
+    JsonApiDataResponse(Ok(JsonApiCollection::data(
+        comments.inner().0.iter().take(count).collect(),
+    )))
+}
+
+fn client() -> Client {
+    let max = SIZES[SIZES.len() - 1] as i64;
+    let articles = (0..max)
+        .map(|id| Article {
+            id,
+            title: format!("Article {}", id),
+            body: "Lorem ipsum dolor sit amet, consectetur adipiscing elit.".to_string(),
+            author: Person { id: id % 100 },
+        })
+        .collect();
+    let comments = (0..max)
+        .map(|id| Comment {
+            id: format!("comment-{}", id),
+            text: "Great article!".to_string(),
+            author: Person { id: id % 100 },
+        })
+        .collect();
+    let rocket = rocket::ignite()
+        .manage(Articles(articles))
+        .manage(Comments(comments))
+        .manage(ResourceRoutes::new().resource::<Comment>("/comments"))
+        .mount("/", routes![articles, comments]);
+    Client::new(rocket).expect("valid rocket instance")
+}
+
+// Dispatches a request and reads the whole body, like a client of the server would
+fn respond(client: &Client, uri: &str) -> usize {
+    let mut response = client.get(uri).dispatch();
+    response.body_bytes().expect("response has a body").len()
+}
+
+fn collections(c: &mut Criterion) {
+    let client = client();
+    for resource in &["articles", "comments"] {
+        let mut group = c.benchmark_group(*resource);
+        for size in SIZES.iter() {
+            let uri = format!("/{}?count={}", resource, size);
+            group.throughput(Throughput::Elements(*size as u64));
+            group.bench_with_input(BenchmarkId::from_parameter(size), &uri, |b, uri| {
+                b.iter(|| respond(&client, uri))
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, collections);
+criterion_main!(benches);
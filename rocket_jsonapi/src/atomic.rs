@@ -0,0 +1,357 @@
+//! # Atomic Operations extension
+//!
+//! Support for the JSON:API v1.1 [Atomic Operations](https://jsonapi.org/ext/atomic/) extension,
+//! which allows performing several operations in a single request, that either all succeed or
+//! all fail.
+//!
+//! The extension must be registered through `MediaTypeSupport`, and requests must be sent with
+//! the `ext` media type parameter:
+//!
+//! ```text
+//! Content-Type: application/vnd.api+json; ext="https://jsonapi.org/ext/atomic"
+//! ```
+//!
+//! ## Example
+//!
+//! ```rust
+//! # #![feature(decl_macro)]
+//! # #[macro_use]
+//! # use rocket::*;
+//! # use rocket_jsonapi::atomic::*;
+//! # use rocket_jsonapi::request::MediaTypeSupport;
+//! # use rocket_jsonapi::{Linkify, ResourceType, ResourceIdentifiable};
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Deserialize)]
+//! struct CreateArticle {
+//!     title: String,
+//! }
+//!
+//! impl ResourceType for CreateArticle {
+//!     fn get_type() -> &'static str {
+//!         "articles"
+//!     }
+//! }
+//!
+//! // Updates only carry the attributes that change
+//! #[derive(Deserialize)]
+//! struct UpdateArticle {
+//!     title: Option<String>,
+//! }
+//!
+//! impl ResourceType for UpdateArticle {
+//!     fn get_type() -> &'static str {
+//!         "articles"
+//!     }
+//! }
+//!
+//! #[derive(Serialize, ResourceType, ResourceIdentifiable, Linkify)]
+//! #[resource_ident_type = "articles"]
+//! struct Article {
+//!     id: String,
+//!     title: String,
+//! }
+//!
+//! #[post("/operations", data = "<operations>")]
+//! fn operations(
+//!     operations: JsonApiAtomicRequest<CreateArticle, UpdateArticle>,
+//! ) -> JsonApiAtomicResponse {
+//!     let mut local_ids = LocalIds::new();
+//!     let mut results = vec![];
+//!     for operation in operations.0 {
+//!         match operation {
+//!             Operation::Add { lid, data } => {
+//!                 let article = Article { id: String::from("1"), title: data.title };
+//!                 if let Some(lid) = lid {
+//!                     local_ids.insert(lid, article.id.clone());
+//!                 }
+//!                 results.push(AtomicResult::data(article));
+//!             }
+//!             _ => results.push(AtomicResult::empty()),
+//!         }
+//!     }
+//!     JsonApiAtomicResponse(Ok(results))
+//! }
+//!
+//! rocket::ignite()
+//!     .manage(MediaTypeSupport::new().extension(ATOMIC_EXTENSION))
+//!     .mount("/", routes![operations]);
+//! ```
+use crate::core::atomic_operations::{parse_operations, AtomicDocument, OPERATIONS};
+use crate::core::data_object::ResourceIdentifiableWrapper;
+use crate::core::link_context::link_context;
+use crate::core::negotiation::{negotiate, response_content_type};
+use crate::error::{JsonApiError, JsonApiErrorSource, JsonApiResponseError};
+use crate::lib::*;
+use crate::request::{acceptable_json_api_data_request, InputResource};
+use rocket::data::{self, FromDataSimple};
+use rocket::http::Status;
+use rocket::response::Responder;
+use rocket::{Data, Request, Response};
+use serde_json::to_string as serialize;
+use std::collections::HashMap;
+use std::io::Cursor;
+
+/// URI identifying the Atomic Operations extension
+pub const ATOMIC_EXTENSION: &str = "https://jsonapi.org/ext/atomic";
+
+/// Identifies a resource either by its server id, or by a local id declared by a preceding `add`
+/// operation of the same request
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ResourceKey {
+    Id(String),
+    Lid(String),
+}
+
+/// Reference to a resource, the `ref` member of an operation
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ResourceRef {
+    pub resource_type: String,
+    pub key: ResourceKey,
+}
+
+/// Target of an operation, either given as a `ref` object or as an `href` URI
+#[derive(Clone, Debug, PartialEq)]
+pub enum OperationTarget {
+    Ref(ResourceRef),
+    Href(String),
+}
+
+/// The `op` code of an operation on a relationship
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RelationshipOp {
+    Add,
+    Update,
+    Remove,
+}
+
+/// Resource linkage of an operation on a relationship
+#[derive(Clone, Debug, PartialEq)]
+pub enum Linkage {
+    ToOne(Option<ResourceRef>),
+    ToMany(Vec<ResourceRef>),
+}
+
+/// A single, typed operation of an atomic operations request
+#[derive(Debug, PartialEq)]
+pub enum Operation<Data, UpdateData = Data> {
+    /// Creates a resource, the optional local id can be referenced by later operations
+    Add { lid: Option<String>, data: Data },
+    /// Updates the targeted resource with the given attributes
+    Update {
+        target: OperationTarget,
+        data: UpdateData,
+    },
+    /// Removes the targeted resource
+    Remove { target: OperationTarget },
+    /// Adds to, replaces or removes from the members of a relationship of the targeted resource
+    Relationship {
+        op: RelationshipOp,
+        target: ResourceRef,
+        relationship: String,
+        data: Linkage,
+    },
+}
+
+/// Data guard for atomic operations requests.
+///
+/// Parses the `atomic:operations` member into typed operations in request order. The resource
+/// objects of `add` operations are deserialized as `Data`, and those of `update` operations as
+/// `UpdateData`, which defaults to `Data`. Both are [InputResource]s, so an enum deriving
+/// `InputResource` lets a single request operate on resources of several types. Each targeted
+/// resource must be of a type accepted by either, and every `lid` must be declared by a
+/// preceding `add` operation.
+///
+/// Fails with 415 Unsupported Media Type if the request is not sent with the Atomic Operations
+/// extension, and with 400 Bad Request, 403 Forbidden or 409 Conflict if an operation is
+/// invalid. The error holds an error object per invalid operation, with a `source.pointer` to it.
+pub struct JsonApiAtomicRequest<Data, UpdateData = Data>(pub Vec<Operation<Data, UpdateData>>);
+
+impl<InputData, UpdateData> FromDataSimple for JsonApiAtomicRequest<InputData, UpdateData>
+where
+    InputData: InputResource,
+    UpdateData: InputResource,
+{
+    type Error = JsonApiResponseError;
+
+    fn from_data(request: &Request, data: Data) -> data::Outcome<Self, Self::Error> {
+        if let Err(status) = acceptable_atomic_request(request) {
+            return data::Outcome::Failure((status, JsonApiResponseError::new(status, vec![])));
+        }
+        // TODO does reader interface open up for DoS attacks
+        let document: Result<AtomicDocument, serde_json::error::Error> =
+            serde_json::from_reader(data.open());
+        match document {
+            Ok(document) => match parse_operations(document.operations) {
+                Ok(operations) => data::Outcome::Success(JsonApiAtomicRequest(operations)),
+                Err(errors) => {
+                    // Operations are atomic, so the first error decides the status
+                    let status = errors[0].status;
+                    data::Outcome::Failure((
+                        status,
+                        JsonApiResponseError::from_items(status, errors),
+                    ))
+                }
+            },
+            Err(err) => data::Outcome::Failure((
+                Status::BadRequest,
+                JsonApiResponseError::from_error(Status::BadRequest, err),
+            )),
+        }
+    }
+}
+
+fn acceptable_atomic_request(request: &Request) -> Result<(), Status> {
+    acceptable_json_api_data_request(request)?;
+    match &negotiate(request).content_type {
+        Some(Ok(params)) if params.extensions.iter().any(|ext| ext == ATOMIC_EXTENSION) => Ok(()),
+        _ => Err(Status::UnsupportedMediaType),
+    }
+}
+
+/// Maps the local ids of created resources to their server ids, so later operations referencing
+/// them can be resolved
+#[derive(Debug, Default)]
+pub struct LocalIds(HashMap<String, String>);
+
+impl LocalIds {
+    pub fn new() -> Self {
+        LocalIds::default()
+    }
+
+    /// Records the server id of the resource created with local id `lid`
+    pub fn insert<L: Into<String>, I: Into<String>>(&mut self, lid: L, id: I) {
+        self.0.insert(lid.into(), id.into());
+    }
+
+    /// Returns the server id of the referenced resource, `None` if it has a local id that is
+    /// not recorded
+    pub fn resolve<'a>(&'a self, resource: &'a ResourceRef) -> Option<&'a str> {
+        match &resource.key {
+            ResourceKey::Id(id) => Some(id),
+            ResourceKey::Lid(lid) => self.0.get(lid).map(String::as_str),
+        }
+    }
+}
+
+/// Result of a single operation, an entry of the `atomic:results` member
+pub struct AtomicResult(Option<Box<dyn ResultData>>);
+
+impl AtomicResult {
+    /// Result of an operation that has no data to return
+    pub fn empty() -> Self {
+        AtomicResult(None)
+    }
+
+    /// Result holding the resource affected by an operation
+    pub fn data<D>(data: D) -> Self
+    where
+        D: Serialize + ResourceIdentifiable + Linkify + 'static,
+    {
+        AtomicResult(Some(Box::new(data)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+}
+
+// The resource of a result, linked like primary data once the link context of the request is
+// known
+trait ResultData {
+    fn in_context<'a>(&'a self, context: &'a LinkContext) -> Box<dyn erased_serde::Serialize + 'a>;
+}
+
+impl<D> ResultData for D
+where
+    D: Serialize + ResourceIdentifiable + Linkify,
+{
+    fn in_context<'a>(&'a self, context: &'a LinkContext) -> Box<dyn erased_serde::Serialize + 'a> {
+        Box::new(ResourceIdentifiableWrapper(self, context))
+    }
+}
+
+struct AtomicResultIn<'a>(&'a AtomicResult, &'a LinkContext);
+
+impl Serialize for AtomicResultIn<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("AtomicResult", 1)?;
+        if let Some(data) = &(self.0).0 {
+            state.serialize_field("data", &data.in_context(self.1))?;
+        }
+        state.end()
+    }
+}
+
+struct AtomicResults<'a>(&'a [AtomicResult], &'a LinkContext);
+
+impl Serialize for AtomicResults<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let results: Vec<_> = self
+            .0
+            .iter()
+            .map(|result| AtomicResultIn(result, self.1))
+            .collect();
+        let mut state = serializer.serialize_struct("AtomicResults", 1)?;
+        state.serialize_field("atomic:results", &results)?;
+        state.end()
+    }
+}
+
+/// Prefixes the `source.pointer` of `error` with the pointer to the operation at `index`, so
+/// errors from processing an operation point into the request document
+pub fn operation_error(index: usize, mut error: JsonApiError) -> JsonApiError {
+    let source = error.source.get_or_insert_with(JsonApiErrorSource::default);
+    source.pointer = Some(format!(
+        "/{}/{}{}",
+        OPERATIONS,
+        index,
+        source.pointer.as_deref().unwrap_or("")
+    ));
+    error
+}
+
+/// # Atomic Operations Responder
+///
+/// Responds with the `atomic:results` of the operations, in request order. If no operation has
+/// a result, it responds with 204 No Content.
+///
+/// On error it responds with the error objects, use `operation_error` to point them to the
+/// operation that failed.
+pub struct JsonApiAtomicResponse(pub Result<Vec<AtomicResult>, JsonApiResponseError>);
+
+impl<'r> Responder<'r> for JsonApiAtomicResponse {
+    fn respond_to(self, request: &Request<'_>) -> Result<Response<'r>, Status> {
+        let content_type = response_content_type(request);
+        match self.0 {
+            Ok(results) => {
+                if results.iter().all(AtomicResult::is_empty) {
+                    return Ok(Response::build()
+                        .header(content_type)
+                        .status(Status::NoContent)
+                        .finalize());
+                }
+                let body = serialize(&AtomicResults(&results, link_context(request)))
+                    .map_err(|_e| Status::InternalServerError)?;
+                Ok(Response::build()
+                    .header(content_type)
+                    .status(Status::Ok)
+                    .sized_body(Cursor::new(body))
+                    .finalize())
+            }
+            Err(error) => {
+                let body = serialize(&error).map_err(|_e| Status::InternalServerError)?;
+                Ok(Response::build()
+                    .header(content_type)
+                    .status(error.0)
+                    .sized_body(Cursor::new(body))
+                    .finalize())
+            }
+        }
+    }
+}
@@ -0,0 +1,643 @@
+use crate::atomic::{
+    Linkage, Operation, OperationTarget, RelationshipOp, ResourceKey, ResourceRef,
+};
+use crate::core::input_data::unaccepted_types;
+use crate::error::{JsonApiError, JsonApiErrorSource};
+use crate::request::InputResource;
+use rocket::http::Status;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+pub const OPERATIONS: &str = "atomic:operations";
+
+/// Data type representing the deserialized document of an atomic operations request. The
+/// operations are kept as raw values, so errors can point to the operation they happen in.
+#[derive(Deserialize)]
+pub struct AtomicDocument {
+    #[serde(rename = "atomic:operations")]
+    pub operations: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum OperationCode {
+    Add,
+    Update,
+    Remove,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawOperation {
+    op: OperationCode,
+    #[serde(rename = "ref")]
+    target: Option<RawRef>,
+    href: Option<String>,
+    #[serde(default)]
+    data: Value,
+    #[serde(rename = "meta")]
+    _meta: Option<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRef {
+    #[serde(rename = "type")]
+    resource_type: String,
+    id: Option<String>,
+    lid: Option<String>,
+    relationship: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawIdentifier {
+    #[serde(rename = "type")]
+    resource_type: String,
+    id: Option<String>,
+    lid: Option<String>,
+    #[serde(rename = "meta")]
+    _meta: Option<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawResource {
+    #[serde(rename = "type")]
+    resource_type: String,
+    id: Option<String>,
+    lid: Option<String>,
+    attributes: Option<Map<String, Value>>,
+}
+
+/// Error in a single operation of an atomic operations request
+#[derive(Debug, PartialEq)]
+pub struct OperationError {
+    pub status: Status,
+    pub pointer: String,
+    pub detail: String,
+}
+
+impl OperationError {
+    fn new<D: ToString>(status: Status, index: usize, path: &str, detail: D) -> Self {
+        OperationError {
+            status,
+            pointer: format!("/{}/{}{}", OPERATIONS, index, path),
+            detail: detail.to_string(),
+        }
+    }
+}
+
+impl From<OperationError> for JsonApiError {
+    fn from(error: OperationError) -> Self {
+        JsonApiError {
+            status: Some(error.status.code.to_string()),
+            detail: Some(error.detail),
+            source: Some(JsonApiErrorSource::pointer(error.pointer)),
+            ..Default::default()
+        }
+    }
+}
+
+/// Parses the raw operations of an atomic operations request in order, resolving `lid`
+/// references against the local ids declared by preceding `add` operations.
+pub fn parse_operations<Data, UpdateData>(
+    operations: Vec<Value>,
+) -> Result<Vec<Operation<Data, UpdateData>>, Vec<OperationError>>
+where
+    Data: InputResource,
+    UpdateData: InputResource,
+{
+    // Maps each declared local id to the type of its resource
+    let mut lids = HashMap::new();
+    let mut parsed = Vec::with_capacity(operations.len());
+    let mut errors = vec![];
+    for (index, operation) in operations.into_iter().enumerate() {
+        match OperationParser::<Data, UpdateData>::new(index, &mut lids).parse(operation) {
+            Ok(operation) => parsed.push(operation),
+            Err(error) => errors.push(error),
+        }
+    }
+    if errors.is_empty() {
+        Ok(parsed)
+    } else {
+        Err(errors)
+    }
+}
+
+struct OperationParser<'a, Data, UpdateData> {
+    index: usize,
+    lids: &'a mut HashMap<String, String>,
+    phantom: std::marker::PhantomData<(Data, UpdateData)>,
+}
+
+impl<'a, Data, UpdateData> OperationParser<'a, Data, UpdateData>
+where
+    Data: InputResource,
+    UpdateData: InputResource,
+{
+    fn new(index: usize, lids: &'a mut HashMap<String, String>) -> Self {
+        OperationParser {
+            index,
+            lids,
+            phantom: std::marker::PhantomData,
+        }
+    }
+
+    fn error<D: ToString>(&self, status: Status, path: &str, detail: D) -> OperationError {
+        OperationError::new(status, self.index, path, detail)
+    }
+
+    fn parse(self, operation: Value) -> Result<Operation<Data, UpdateData>, OperationError> {
+        let raw: RawOperation = serde_json::from_value(operation)
+            .map_err(|err| self.error(Status::BadRequest, "", err))?;
+        if raw.target.is_some() && raw.href.is_some() {
+            return Err(self.error(
+                Status::BadRequest,
+                "",
+                "an operation must not contain both `ref` and `href`",
+            ));
+        }
+        match &raw.target {
+            Some(RawRef {
+                relationship: Some(_),
+                ..
+            }) => self.parse_relationship(raw),
+            _ => match raw.op {
+                OperationCode::Add => self.parse_add(raw),
+                OperationCode::Update => self.parse_update(raw),
+                OperationCode::Remove => self.parse_remove(raw),
+            },
+        }
+    }
+
+    fn parse_add(self, raw: RawOperation) -> Result<Operation<Data, UpdateData>, OperationError> {
+        if raw.target.is_some() {
+            return Err(self.error(
+                Status::BadRequest,
+                "/ref",
+                "`ref` of an add operation must target a relationship",
+            ));
+        }
+        let resource: RawResource = serde_json::from_value(raw.data)
+            .map_err(|err| self.error(Status::BadRequest, "/data", err))?;
+        if !Data::accepts_type(&resource.resource_type) {
+            return Err(self.unaccepted_type(
+                &resource.resource_type,
+                &Data::input_types(),
+                "/data/type",
+            ));
+        }
+        if resource.id.is_some() {
+            // A server MUST return 403 Forbidden in response to an unsupported request to create
+            // a resource with a client-generated ID.
+            return Err(self.error(
+                Status::Forbidden,
+                "/data/id",
+                "client generated ids are not supported",
+            ));
+        }
+        if let Some(lid) = &resource.lid {
+            if self.lids.contains_key(lid) {
+                return Err(self.error(
+                    Status::BadRequest,
+                    "/data/lid",
+                    format!("local id `{}` is already declared", lid),
+                ));
+            }
+            self.lids
+                .insert(lid.clone(), resource.resource_type.clone());
+        }
+        let attributes = Value::Object(resource.attributes.unwrap_or_default());
+        let data = Data::deserialize_input(&resource.resource_type, attributes)
+            .map_err(|err| self.error(Status::Conflict, "/data/attributes", err))?;
+        Ok(Operation::Add {
+            lid: resource.lid,
+            data,
+        })
+    }
+
+    fn parse_update(
+        self,
+        raw: RawOperation,
+    ) -> Result<Operation<Data, UpdateData>, OperationError> {
+        let resource: RawResource = serde_json::from_value(raw.data)
+            .map_err(|err| self.error(Status::BadRequest, "/data", err))?;
+        if !UpdateData::accepts_type(&resource.resource_type) {
+            return Err(self.unaccepted_type(
+                &resource.resource_type,
+                &UpdateData::input_types(),
+                "/data/type",
+            ));
+        }
+        let attributes = Value::Object(resource.attributes.unwrap_or_default());
+        let data = UpdateData::deserialize_input(&resource.resource_type, attributes)
+            .map_err(|err| self.error(Status::Conflict, "/data/attributes", err))?;
+        let data_ref =
+            self.resource_ref(resource.resource_type, resource.id, resource.lid, "/data")?;
+        let target = match (raw.target, raw.href) {
+            (Some(target), _) => {
+                let target =
+                    self.resource_ref(target.resource_type, target.id, target.lid, "/ref")?;
+                if target != data_ref {
+                    // A server MUST return 409 Conflict when the type and id of the resource
+                    // object do not match the targeted resource.
+                    return Err(self.error(
+                        Status::Conflict,
+                        "/data",
+                        "resource object does not match `ref`",
+                    ));
+                }
+                OperationTarget::Ref(target)
+            }
+            (None, Some(href)) => OperationTarget::Href(href),
+            (None, None) => OperationTarget::Ref(data_ref),
+        };
+        Ok(Operation::Update { target, data })
+    }
+
+    fn parse_remove(
+        self,
+        raw: RawOperation,
+    ) -> Result<Operation<Data, UpdateData>, OperationError> {
+        let target = match (raw.target, raw.href) {
+            (Some(target), _) => {
+                self.check_type(&target.resource_type, "/ref/type")?;
+                OperationTarget::Ref(self.resource_ref(
+                    target.resource_type,
+                    target.id,
+                    target.lid,
+                    "/ref",
+                )?)
+            }
+            (None, Some(href)) => OperationTarget::Href(href),
+            (None, None) => {
+                return Err(self.error(
+                    Status::BadRequest,
+                    "",
+                    "a remove operation must contain `ref` or `href`",
+                ))
+            }
+        };
+        Ok(Operation::Remove { target })
+    }
+
+    fn parse_relationship(
+        self,
+        raw: RawOperation,
+    ) -> Result<Operation<Data, UpdateData>, OperationError> {
+        let target = raw.target.expect("relationship operations have a `ref`");
+        let relationship = target.relationship.unwrap_or_default();
+        self.check_type(&target.resource_type, "/ref/type")?;
+        let target = self.resource_ref(target.resource_type, target.id, target.lid, "/ref")?;
+        let op = match raw.op {
+            OperationCode::Add => RelationshipOp::Add,
+            OperationCode::Update => RelationshipOp::Update,
+            OperationCode::Remove => RelationshipOp::Remove,
+        };
+        let data = match raw.data {
+            Value::Array(identifiers) => {
+                let mut linkage = Vec::with_capacity(identifiers.len());
+                for (i, identifier) in identifiers.into_iter().enumerate() {
+                    linkage.push(self.identifier(identifier, &format!("/data/{}", i))?);
+                }
+                Linkage::ToMany(linkage)
+            }
+            // Only the full replacement of a to-one relationship is possible
+            Value::Null if op == RelationshipOp::Update => Linkage::ToOne(None),
+            identifier @ Value::Object(_) if op == RelationshipOp::Update => {
+                Linkage::ToOne(Some(self.identifier(identifier, "/data")?))
+            }
+            _ => {
+                return Err(self.error(
+                    Status::BadRequest,
+                    "/data",
+                    "data must be an array of resource identifier objects",
+                ))
+            }
+        };
+        Ok(Operation::Relationship {
+            op,
+            target,
+            relationship,
+            data,
+        })
+    }
+
+    fn identifier(&self, identifier: Value, path: &str) -> Result<ResourceRef, OperationError> {
+        let identifier: RawIdentifier = serde_json::from_value(identifier)
+            .map_err(|err| self.error(Status::BadRequest, path, err))?;
+        self.resource_ref(
+            identifier.resource_type,
+            identifier.id,
+            identifier.lid,
+            path,
+        )
+    }
+
+    /// Checks that a targeted resource is of one of the types accepted by the endpoint, for
+    /// creating or for updating resources
+    fn check_type(&self, resource_type: &str, path: &str) -> Result<(), OperationError> {
+        if Data::accepts_type(resource_type) || UpdateData::accepts_type(resource_type) {
+            return Ok(());
+        }
+        let mut accepted = Data::input_types();
+        for update_type in UpdateData::input_types() {
+            if !accepted.contains(&update_type) {
+                accepted.push(update_type);
+            }
+        }
+        Err(self.unaccepted_type(resource_type, &accepted, path))
+    }
+
+    fn unaccepted_type(
+        &self,
+        resource_type: &str,
+        accepted: &[&'static str],
+        path: &str,
+    ) -> OperationError {
+        let err: serde_json::Error = unaccepted_types(resource_type, accepted);
+        self.error(Status::Conflict, path, err)
+    }
+
+    fn resource_ref(
+        &self,
+        resource_type: String,
+        id: Option<String>,
+        lid: Option<String>,
+        path: &str,
+    ) -> Result<ResourceRef, OperationError> {
+        let key = match (id, lid) {
+            (Some(id), None) => ResourceKey::Id(id),
+            (None, Some(lid)) => {
+                match self.lids.get(&lid) {
+                    Some(lid_type) if *lid_type == resource_type => {}
+                    Some(lid_type) => {
+                        return Err(self.error(
+                            Status::Conflict,
+                            &format!("{}/type", path),
+                            format!(
+                                "local id `{}` identifies a resource of type `{}`",
+                                lid, lid_type
+                            ),
+                        ))
+                    }
+                    None => {
+                        return Err(self.error(
+                            Status::BadRequest,
+                            &format!("{}/lid", path),
+                            format!(
+                                "local id `{}` is not declared by a preceding operation",
+                                lid
+                            ),
+                        ))
+                    }
+                }
+                ResourceKey::Lid(lid)
+            }
+            _ => {
+                return Err(self.error(
+                    Status::BadRequest,
+                    path,
+                    "exactly one of `id` and `lid` must be given",
+                ))
+            }
+        };
+        Ok(ResourceRef { resource_type, key })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::atomic::{
+        Linkage, Operation, OperationTarget, RelationshipOp, ResourceKey, ResourceRef,
+    };
+    use crate::core::atomic_operations::{parse_operations, AtomicDocument, OperationError};
+    use crate::ResourceType;
+    use rocket::http::Status;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    struct Test {
+        message: String,
+    }
+
+    impl ResourceType for Test {
+        fn get_type() -> &'static str {
+            &"Test"
+        }
+    }
+
+    fn parse(raw: &str) -> Result<Vec<Operation<Test>>, Vec<OperationError>> {
+        let document: AtomicDocument = serde_json::from_str(raw).unwrap();
+        parse_operations(document.operations)
+    }
+
+    fn lid_ref(resource_type: &str, lid: &str) -> ResourceRef {
+        ResourceRef {
+            resource_type: resource_type.to_string(),
+            key: ResourceKey::Lid(lid.to_string()),
+        }
+    }
+
+    #[test]
+    fn parse_add_update_remove_with_lid() {
+        let operations = parse(
+            r#"
+            {
+                "atomic:operations": [{
+                    "op": "add",
+                    "data": {
+                        "type": "Test",
+                        "lid": "a",
+                        "attributes": { "message": "Hello" }
+                    }
+                }, {
+                    "op": "update",
+                    "data": {
+                        "type": "Test",
+                        "lid": "a",
+                        "attributes": { "message": "Hallo" }
+                    }
+                }, {
+                    "op": "remove",
+                    "ref": { "type": "Test", "id": "13" }
+                }]
+            }
+        "#,
+        )
+        .unwrap();
+        assert_eq!(operations.len(), 3);
+        assert_eq!(
+            operations[0],
+            Operation::Add {
+                lid: Some("a".to_string()),
+                data: Test {
+                    message: "Hello".to_string()
+                }
+            }
+        );
+        assert_eq!(
+            operations[1],
+            Operation::Update {
+                target: OperationTarget::Ref(lid_ref("Test", "a")),
+                data: Test {
+                    message: "Hallo".to_string()
+                }
+            }
+        );
+        assert_eq!(
+            operations[2],
+            Operation::Remove {
+                target: OperationTarget::Ref(ResourceRef {
+                    resource_type: "Test".to_string(),
+                    key: ResourceKey::Id("13".to_string()),
+                })
+            }
+        );
+    }
+
+    #[test]
+    fn parse_relationship_operation() {
+        let operations = parse(
+            r#"
+            {
+                "atomic:operations": [{
+                    "op": "add",
+                    "ref": { "type": "Test", "id": "1", "relationship": "comments" },
+                    "data": [{ "type": "comments", "id": "12" }]
+                }, {
+                    "op": "update",
+                    "ref": { "type": "Test", "id": "1", "relationship": "author" },
+                    "data": null
+                }]
+            }
+        "#,
+        )
+        .unwrap();
+        match &operations[0] {
+            Operation::Relationship {
+                op,
+                relationship,
+                data: Linkage::ToMany(data),
+                ..
+            } => {
+                assert_eq!(*op, RelationshipOp::Add);
+                assert_eq!(relationship, "comments");
+                assert_eq!(data.len(), 1);
+            }
+            other => panic!("unexpected operation {:?}", other),
+        }
+        match &operations[1] {
+            Operation::Relationship { op, data, .. } => {
+                assert_eq!(*op, RelationshipOp::Update);
+                assert_eq!(*data, Linkage::ToOne(None));
+            }
+            other => panic!("unexpected operation {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_invalid_type_points_to_operation() {
+        let errors = parse(
+            r#"
+            {
+                "atomic:operations": [{
+                    "op": "remove",
+                    "ref": { "type": "Test", "id": "1" }
+                }, {
+                    "op": "add",
+                    "data": {
+                        "type": "NotTest",
+                        "attributes": { "message": "Hello" }
+                    }
+                }]
+            }
+        "#,
+        )
+        .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].status, Status::Conflict);
+        assert_eq!(errors[0].pointer, "/atomic:operations/1/data/type");
+    }
+
+    #[test]
+    fn parse_undeclared_lid() {
+        let errors = parse(
+            r#"
+            {
+                "atomic:operations": [{
+                    "op": "remove",
+                    "ref": { "type": "Test", "lid": "a" }
+                }, {
+                    "op": "add",
+                    "data": {
+                        "type": "Test",
+                        "lid": "a",
+                        "attributes": { "message": "Hello" }
+                    }
+                }]
+            }
+        "#,
+        )
+        .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].status, Status::BadRequest);
+        assert_eq!(errors[0].pointer, "/atomic:operations/0/ref/lid");
+    }
+
+    #[test]
+    fn parse_duplicate_lid() {
+        let errors = parse(
+            r#"
+            {
+                "atomic:operations": [{
+                    "op": "add",
+                    "data": { "type": "Test", "lid": "a", "attributes": { "message": "1" } }
+                }, {
+                    "op": "add",
+                    "data": { "type": "Test", "lid": "a", "attributes": { "message": "2" } }
+                }]
+            }
+        "#,
+        )
+        .unwrap_err();
+        assert_eq!(errors[0].pointer, "/atomic:operations/1/data/lid");
+    }
+
+    #[test]
+    fn parse_invalid_attributes() {
+        let errors = parse(
+            r#"
+            {
+                "atomic:operations": [{
+                    "op": "add",
+                    "data": { "type": "Test", "attributes": { "not_message": "Hello" } }
+                }]
+            }
+        "#,
+        )
+        .unwrap_err();
+        assert_eq!(errors[0].status, Status::Conflict);
+        assert_eq!(errors[0].pointer, "/atomic:operations/0/data/attributes");
+    }
+
+    #[test]
+    fn parse_unknown_op() {
+        let errors = parse(
+            r#"
+            {
+                "atomic:operations": [{
+                    "op": "replace",
+                    "ref": { "type": "Test", "id": "1" }
+                }]
+            }
+        "#,
+        )
+        .unwrap_err();
+        assert_eq!(errors[0].status, Status::BadRequest);
+        assert_eq!(errors[0].pointer, "/atomic:operations/0");
+    }
+}
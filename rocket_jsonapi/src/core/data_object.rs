@@ -1,54 +1,114 @@
-use crate::core::links_serialize::LinksSerialize;
-use crate::core::serialize_no_conversion::CanSerializeNoConversion;
+use crate::core::links_serialize::TopLevelLinksSerialize;
+use crate::core::relationships_serialize::{MaybeRelationships, RelationshipsSerialize};
+use crate::core::resource_identifier::MaybeInstanceType;
+use crate::core::serialize_no_conversion::IdSerialize;
 use crate::lib::*;
-use crate::response::JsonApiCollection;
+use crate::resource::ResourceMeta;
+use crate::response::{CollectionLinks, JsonApiCollection};
+use std::borrow::Cow;
 
-// Struct for data, will be parsed correctly
-pub struct JsonApiPrimaryDataObject<'a, Data>(pub &'a Data);
+// Struct for data, will be parsed correctly. Links are generated in the given context
+pub struct JsonApiPrimaryDataObject<'a, Data>(pub &'a Data, pub &'a LinkContext);
 
 impl<'a, Data> Serialize for JsonApiPrimaryDataObject<'a, Data>
 where
     Data: Serialize + ResourceIdentifiable + Linkify,
 {
     default fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let links = self.0.get_links_in(self.1);
+        JsonApiResourceDataObject(self.0, &links, self.1).serialize(serializer)
+    }
+}
+
+/// Data object of a single resource with its links computed beforehand, e.g. when they also give
+/// the `Location` of a created resource
+pub struct JsonApiResourceDataObject<'a, R>(pub &'a R, pub &'a [Link<'a>], pub &'a LinkContext);
+
+impl<'a, R> Serialize for JsonApiResourceDataObject<'a, R>
+where
+    R: Serialize + ResourceIdentifiable + Linkify,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
         let mut state = serializer.serialize_struct("JsonApiPrimaryDataObject", 3)?;
-        state.serialize_field("data", &ResourceIdentifiableWrapper(self.0))?;
-        let links = self.0.get_links();
+        // The `self` link is the URL of the resource, so it isn't looked up again for the links
+        // of its relationships
+        let resource_url = match self.1.iter().find(|link| link.key() == "self") {
+            Some(link) => Some(Cow::Borrowed(link.href())),
+            None => self.2.resource_route_url(self.0).map(Cow::Owned),
+        };
+        state.serialize_field(
+            "data",
+            &ResourceObject(self.0, resource_url.as_deref(), self.2),
+        )?;
+        let links = TopLevelLinksSerialize(self.1, self.2.self_link(), self.2.related_link());
         if !links.is_empty() {
-            state.serialize_field("links", &LinksSerialize(&links))?;
+            state.serialize_field("links", &links)?;
         }
         // TODO Includify and Relationships
         state.end()
     }
 }
 
-impl<'a, Data> Serialize for JsonApiPrimaryDataObject<'a, JsonApiCollection<Data>>
+impl<'a, Data, Links> Serialize for JsonApiPrimaryDataObject<'a, JsonApiCollection<Data, Links>>
 where
     Data: Serialize + ResourceIdentifiable + Linkify,
+    Links: CollectionLinks,
 {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("JsonApiPrimaryDataObject", 3)?;
-        state.serialize_field("data", &JsonApiPrimaryDataObjectArray(&(self.0).0))?;
-        let links = &(self.0).1;
+        let mut state = serializer.serialize_struct("JsonApiPrimaryDataObject", 4)?;
+        state.serialize_field("data", &JsonApiPrimaryDataObjectArray(&(self.0).0, self.1))?;
+        let links = TopLevelLinksSerialize(
+            (self.0).1.links(),
+            self.1.self_link(),
+            self.1.related_link(),
+        );
         if !links.is_empty() {
-            state.serialize_field("links", &LinksSerialize(links))?;
+            state.serialize_field("links", &links)?;
+        }
+        if let Some(meta) = (self.0).1.meta() {
+            state.serialize_field("meta", meta)?;
         }
         // TODO Includify and Relationships
         state.end()
     }
 }
 
-struct JsonApiPrimaryDataObjectArray<'a, Data>(&'a Vec<Data>);
+// Nullable primary data, e.g. of an empty to-one related resource endpoint
+impl<'a, Data> Serialize for JsonApiPrimaryDataObject<'a, Option<Data>>
+where
+    Data: Serialize + ResourceIdentifiable + Linkify,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if let Some(data) = self.0 {
+            return JsonApiPrimaryDataObject(data, self.1).serialize(serializer);
+        }
+        let mut state = serializer.serialize_struct("JsonApiPrimaryDataObject", 3)?;
+        state.serialize_field("data", &())?;
+        let links = TopLevelLinksSerialize(&[], self.1.self_link(), self.1.related_link());
+        if !links.is_empty() {
+            state.serialize_field("links", &links)?;
+        }
+        state.end()
+    }
+}
+
+struct JsonApiPrimaryDataObjectArray<'a, Data>(&'a Vec<Data>, &'a LinkContext);
 
 impl<'a, Data> Serialize for JsonApiPrimaryDataObjectArray<'a, Data>
 where
-    Data: Serialize + ResourceIdentifiable,
+    Data: Serialize + ResourceIdentifiable + Linkify,
 {
     fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
     where
@@ -56,51 +116,113 @@ where
     {
         let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
         for res in self.0 {
-            seq.serialize_element(&ResourceIdentifiableWrapper(res))?;
+            seq.serialize_element(&ResourceIdentifiableWrapper(res, self.1))?;
         }
         seq.end()
     }
 }
 
+/// Serializes the `meta` member of a resource object, if the resource implements `ResourceMeta`,
+/// or the meta of the resource it wraps if it implements `ForwardResourceMeta`
+pub trait MaybeResourceMeta {
+    fn serialize_meta<S: SerializeStruct>(&self, state: &mut S) -> Result<(), S::Error>;
+}
+
+impl<T> MaybeResourceMeta for T {
+    default fn serialize_meta<S: SerializeStruct>(&self, state: &mut S) -> Result<(), S::Error> {
+        MaybeForwardResourceMeta::forward_meta(self, state)
+    }
+}
+
+impl<T: ResourceMeta> MaybeResourceMeta for T {
+    fn serialize_meta<S: SerializeStruct>(&self, state: &mut S) -> Result<(), S::Error> {
+        state.serialize_field("meta", &self.get_meta())
+    }
+}
+
+/// Implemented by resources wrapping other resources, like transparent resources, to serialize the
+/// meta of the wrapped resource through `MaybeResourceMeta`, whether it has meta or not
+pub trait ForwardResourceMeta {
+    fn forward_meta<S: SerializeStruct>(&self, state: &mut S) -> Result<(), S::Error>;
+}
+
+// Resources without `ResourceMeta` have the meta of the resource they wrap, if any
+trait MaybeForwardResourceMeta {
+    fn forward_meta<S: SerializeStruct>(&self, state: &mut S) -> Result<(), S::Error>;
+}
+
+impl<T> MaybeForwardResourceMeta for T {
+    default fn forward_meta<S: SerializeStruct>(&self, _state: &mut S) -> Result<(), S::Error> {
+        Ok(())
+    }
+}
+
+impl<T: ForwardResourceMeta> MaybeForwardResourceMeta for T {
+    fn forward_meta<S: SerializeStruct>(&self, state: &mut S) -> Result<(), S::Error> {
+        ForwardResourceMeta::forward_meta(self, state)
+    }
+}
+
 // Newtype to customize parsing of ResourceIdentifiable, so we don't need to allocate a new data
-// structure
-struct ResourceIdentifiableWrapper<'a, R>(&'a R);
+// structure. Links of its relationships are generated in the given context
+pub struct ResourceIdentifiableWrapper<'a, R>(pub &'a R, pub &'a LinkContext);
 
 impl<'a, R> Serialize for ResourceIdentifiableWrapper<'a, R>
 where
-    R: Serialize + ResourceIdentifiable,
+    R: Serialize + ResourceIdentifiable + Linkify,
 {
-    default fn serialize<S>(
-        &self,
-        serializer: S,
-    ) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("ResourceIdentifier", 3)?;
-        state.serialize_field("id", &self.0.get_id().to_string())?;
-        state.serialize_field("type", R::get_type())?;
-        state.serialize_field("attributes", &self.0)?;
-        state.end()
+        serialize_resource(
+            self.0,
+            || self.1.resource_url(self.0).map(Cow::Owned),
+            serializer,
+        )
     }
 }
 
-// Specialized case where we can simply read the Id, without having to convert it to a string first
-impl<'a, Data> Serialize for ResourceIdentifiableWrapper<'a, Data>
+// Resource object of a resource whose URL is known already
+struct ResourceObject<'a, R>(&'a R, Option<&'a str>, &'a LinkContext);
+
+impl<'a, R> Serialize for ResourceObject<'a, R>
 where
-    Data: Serialize + ResourceIdentifiable<IdType: CanSerializeNoConversion>,
+    R: Serialize + ResourceIdentifiable + Linkify,
 {
     fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("ResourceIdentifier", 3)?;
-        // Specialized part, here we simply read the Id value, no conversion needed
-        state.serialize_field("id", &self.0.get_id().as_str())?;
-        state.serialize_field("type", Data::get_type())?;
-        state.serialize_field("attributes", &self.0)?;
-        state.end()
+        serialize_resource(self.0, || self.1.map(Cow::Borrowed), serializer)
+    }
+}
+
+// The URL of the resource is only needed for the links of its relationships, so it is computed
+// only if the resource has relationships
+fn serialize_resource<'u, R, S>(
+    resource: &R,
+    resource_url: impl FnOnce() -> Option<Cow<'u, str>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    R: Serialize + ResourceIdentifiable + Linkify,
+    S: Serializer,
+{
+    let mut state = serializer.serialize_struct("ResourceIdentifier", 5)?;
+    state.serialize_field("id", &IdSerialize(resource.get_id()))?;
+    state.serialize_field("type", resource.type_of())?;
+    state.serialize_field("attributes", resource)?;
+    let relationships = resource.relation_objects();
+    if !relationships.is_empty() {
+        let resource_url = resource_url();
+        state.serialize_field(
+            "relationships",
+            &RelationshipsSerialize(&relationships, resource_url.as_deref()),
+        )?;
     }
+    resource.serialize_meta(&mut state)?;
+    state.end()
 }
 
 #[cfg(test)]
@@ -108,7 +230,8 @@ mod tests {
     use crate::core::data_object::{
         JsonApiPrimaryDataObject, JsonApiPrimaryDataObjectArray, ResourceIdentifiableWrapper,
     };
-    use crate::resource::ResourceType;
+    use crate::links::LinkContext;
+    use crate::resource::{Meta, ResourceMeta, ResourceType};
     use crate::response::JsonApiCollection;
     use crate::{Linkify, ResourceIdentifiable};
     use serde::Serialize;
@@ -142,8 +265,11 @@ mod tests {
             id: 5,
             message: "Hello".to_string(),
         };
-        let test_instance_value =
-            serde_json::to_value(ResourceIdentifiableWrapper(&test_instance)).unwrap();
+        let test_instance_value = serde_json::to_value(ResourceIdentifiableWrapper(
+            &test_instance,
+            &LinkContext::default(),
+        ))
+        .unwrap();
         let test_equals_value = json!({
             "id": "5",
             "type": "Test",
@@ -174,12 +300,16 @@ mod tests {
                 &self.id
             }
         }
+        impl Linkify for T {}
         let test_instance = T {
             id: Box::new("12".to_string()),
             message: "Hello".to_string(),
         };
-        let test_instance_value =
-            serde_json::to_value(ResourceIdentifiableWrapper(&test_instance)).unwrap();
+        let test_instance_value = serde_json::to_value(ResourceIdentifiableWrapper(
+            &test_instance,
+            &LinkContext::default(),
+        ))
+        .unwrap();
         let test_equals_value = json!({
             "id": "12",
             "type": "T",
@@ -191,14 +321,67 @@ mod tests {
         assert_eq!(test_instance_value, test_equals_value);
     }
 
+    #[test]
+    fn serialize_resource_identifiable_wrapper_meta() {
+        #[derive(Serialize)]
+        struct T {
+            id: i32,
+            message: String,
+        }
+        impl ResourceType for T {
+            fn get_type() -> &'static str {
+                &"T"
+            }
+        }
+        impl ResourceIdentifiable for T {
+            type IdType = i32;
+
+            fn get_id(&self) -> &Self::IdType {
+                &self.id
+            }
+        }
+        impl Linkify for T {}
+        impl ResourceMeta for T {
+            type Meta = Meta;
+
+            fn get_meta(&self) -> Self::Meta {
+                Meta::new().with("length", self.message.len())
+            }
+        }
+        let test_instance = T {
+            id: 5,
+            message: "Hello".to_string(),
+        };
+        let test_instance_value = serde_json::to_value(ResourceIdentifiableWrapper(
+            &test_instance,
+            &LinkContext::default(),
+        ))
+        .unwrap();
+        let test_equals_value = json!({
+            "id": "5",
+            "type": "T",
+            "attributes": {
+                "id": 5,
+                "message": "Hello"
+            },
+            "meta": {
+                "length": 5
+            }
+        });
+        assert_eq!(test_instance_value, test_equals_value);
+    }
+
     #[test]
     fn serialize_json_primary_data_object() {
         let test_instance = Test {
             id: 5,
             message: "Hello".to_string(),
         };
-        let test_instance_value =
-            serde_json::to_value(JsonApiPrimaryDataObject(&test_instance)).unwrap();
+        let test_instance_value = serde_json::to_value(JsonApiPrimaryDataObject(
+            &test_instance,
+            &LinkContext::default(),
+        ))
+        .unwrap();
         let test_equals_value = json!({
             "data": {
                 "id": "5",
@@ -222,12 +405,11 @@ mod tests {
             id: 6,
             message: "Hallo".to_string(),
         };
-        let test_instance_value =
-            serde_json::to_value(JsonApiPrimaryDataObject(&JsonApiCollection::data(vec![
-                test_instance1,
-                test_instance2,
-            ])))
-            .unwrap();
+        let test_instance_value = serde_json::to_value(JsonApiPrimaryDataObject(
+            &JsonApiCollection::data(vec![test_instance1, test_instance2]),
+            &LinkContext::default(),
+        ))
+        .unwrap();
         let test_equals_value = json!({
             "data": [{
                 "id": "5",
@@ -248,6 +430,45 @@ mod tests {
         assert_eq!(test_instance_value, test_equals_value);
     }
 
+    #[test]
+    fn serialize_json_primary_data_object_option() {
+        let test_instance = Some(Test {
+            id: 5,
+            message: "Hello".to_string(),
+        });
+        let context = LinkContext::default().with_self_link("/tests/5");
+        let test_instance_value =
+            serde_json::to_value(JsonApiPrimaryDataObject(&test_instance, &context)).unwrap();
+        let test_equals_value = json!({
+            "data": {
+                "id": "5",
+                "type": "Test",
+                "attributes": {
+                    "id": 5,
+                    "message": "Hello"
+                }
+            },
+            "links": {
+                "self": "/tests/5"
+            }
+        });
+        assert_eq!(test_instance_value, test_equals_value);
+    }
+
+    #[test]
+    fn serialize_json_primary_data_object_none() {
+        let context = LinkContext::default().with_self_link("/tests/5/parent");
+        let test_instance_value =
+            serde_json::to_value(JsonApiPrimaryDataObject(&None::<Test>, &context)).unwrap();
+        let test_equals_value = json!({
+            "data": null,
+            "links": {
+                "self": "/tests/5/parent"
+            }
+        });
+        assert_eq!(test_instance_value, test_equals_value);
+    }
+
     #[test]
     fn serialize_json_primary_data_object_array() {
         let test_instance1 = Test {
@@ -258,10 +479,10 @@ mod tests {
             id: 6,
             message: "Hallo".to_string(),
         };
-        let test_instance_value = serde_json::to_value(JsonApiPrimaryDataObjectArray(&vec![
-            test_instance1,
-            test_instance2,
-        ]))
+        let test_instance_value = serde_json::to_value(JsonApiPrimaryDataObjectArray(
+            &vec![test_instance1, test_instance2],
+            &LinkContext::default(),
+        ))
         .unwrap();
         let test_equals_value = json!([{
             "id": "5",
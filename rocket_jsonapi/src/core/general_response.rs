@@ -1,12 +1,12 @@
-use crate::core::data_object::JsonApiPrimaryDataObject;
+use crate::core::data_object::{JsonApiPrimaryDataObject, JsonApiResourceDataObject};
+use crate::core::link_context::link_context;
+use crate::core::negotiation::response_content_type;
 use crate::error::JsonApiError;
 use crate::lib::*;
-use crate::relationship::Relationships;
-use crate::response::JsonApiCollection;
-use rocket::http::{ContentType, Status};
+use crate::response::{CollectionLinks, JsonApiCollection};
+use rocket::http::{ContentType, Header, Status};
 use rocket::response::Responder;
 use rocket::{Request, Response};
-use serde_json::to_string as serialize;
 use std::io::Cursor;
 
 struct ResponseError<'a>(&'a Vec<JsonApiError>);
@@ -24,7 +24,10 @@ impl Serialize for ResponseError<'_> {
 
 pub struct JsonApiResponse<Data>(pub Status, pub Result<Data, Vec<JsonApiError>>);
 
-impl<Data> Serialize for JsonApiResponse<Data>
+/// Top-level document of a response, with links generated in the given context
+pub struct JsonApiDocument<'a, Data>(pub &'a JsonApiResponse<Data>, pub &'a LinkContext);
+
+impl<Data> Serialize for JsonApiDocument<'_, Data>
 where
     // TODO implement Includify + Relationships
     Data: Serialize + ResourceIdentifiable + Linkify,
@@ -33,48 +36,149 @@ where
     where
         S: Serializer,
     {
-        match &self.1 {
-            Ok(api_result) => serializer.serialize_some(&JsonApiPrimaryDataObject(api_result)),
+        match &(self.0).1 {
+            Ok(api_result) => {
+                serializer.serialize_some(&JsonApiPrimaryDataObject(api_result, self.1))
+            }
             Err(err) => serializer.serialize_some(&ResponseError(err)),
         }
         // TODO handle json_api field
     }
 }
 
-impl<Data> Serialize for JsonApiResponse<JsonApiCollection<Data>>
+impl<Data, Links> Serialize for JsonApiDocument<'_, JsonApiCollection<Data, Links>>
 where
     // TODO implement Includify + Relationships
     Data: Serialize + ResourceIdentifiable + Linkify,
+    Links: CollectionLinks,
 {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        match &self.1 {
-            Ok(api_result) => serializer.serialize_some(&JsonApiPrimaryDataObject(api_result)),
+        match &(self.0).1 {
+            Ok(api_result) => {
+                serializer.serialize_some(&JsonApiPrimaryDataObject(api_result, self.1))
+            }
             Err(err) => serializer.serialize_some(&ResponseError(err)),
         }
         // TODO handle json_api field
     }
 }
 
+impl<Data> Serialize for JsonApiDocument<'_, Option<Data>>
+where
+    Data: Serialize + ResourceIdentifiable + Linkify,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match &(self.0).1 {
+            Ok(api_result) => {
+                serializer.serialize_some(&JsonApiPrimaryDataObject(api_result, self.1))
+            }
+            Err(err) => serializer.serialize_some(&ResponseError(err)),
+        }
+    }
+}
+
+impl<Data> Serialize for JsonApiResponse<Data>
+where
+    Data: Serialize + ResourceIdentifiable + Linkify,
+{
+    default fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // Outside of a request, links are generated without a base URL
+        JsonApiDocument(self, &LinkContext::default()).serialize(serializer)
+    }
+}
+
+impl<Data, Links> Serialize for JsonApiResponse<JsonApiCollection<Data, Links>>
+where
+    Data: Serialize + ResourceIdentifiable + Linkify,
+    Links: CollectionLinks,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        JsonApiDocument(self, &LinkContext::default()).serialize(serializer)
+    }
+}
+
+impl<Data> Serialize for JsonApiResponse<Option<Data>>
+where
+    Data: Serialize + ResourceIdentifiable + Linkify,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        JsonApiDocument(self, &LinkContext::default()).serialize(serializer)
+    }
+}
+
+/// Bytes reserved for the body of a response, besides its resources
+const DOCUMENT_SIZE_HINT: usize = 256;
+/// Bytes reserved per resource, enough for a small resource object with a few relationships
+const RESOURCE_SIZE_HINT: usize = 256;
+
+// Expected size of the serialized document, so large collections are serialized into a buffer
+// without growing it over and over
+trait BodySizeHint {
+    fn body_size_hint(&self) -> usize;
+}
+
+impl<Data> BodySizeHint for JsonApiResponse<Data> {
+    default fn body_size_hint(&self) -> usize {
+        DOCUMENT_SIZE_HINT + RESOURCE_SIZE_HINT
+    }
+}
+
+impl<Data, Links> BodySizeHint for JsonApiResponse<JsonApiCollection<Data, Links>> {
+    fn body_size_hint(&self) -> usize {
+        match &self.1 {
+            Ok(collection) => DOCUMENT_SIZE_HINT + collection.0.len() * RESOURCE_SIZE_HINT,
+            Err(_) => DOCUMENT_SIZE_HINT,
+        }
+    }
+}
+
+// Serializes the document of `response` straight into the buffer used as response body
+fn serialize<Data>(
+    response: &JsonApiResponse<Data>,
+    context: &LinkContext,
+) -> Result<Vec<u8>, Status>
+where
+    for<'a> JsonApiDocument<'a, Data>: Serialize,
+{
+    let mut body = Vec::with_capacity(response.body_size_hint());
+    serde_json::to_writer(&mut body, &JsonApiDocument(response, context))
+        .map_err(|_e| Status::InternalServerError)?;
+    Ok(body)
+}
+
 fn construct_response<'r, Data>(
-    response_body: String,
+    response_body: Vec<u8>,
     response: Result<Data, Vec<JsonApiError>>,
     status: Status,
+    content_type: ContentType,
 ) -> Result<Response<'r>, Status>
 //where
 //    Data: Serialize + ResourceIdentifiable + Linkify,
 {
     match response {
         Ok(_data) => Ok(Response::build()
-            .header(ContentType::JsonApi)
+            .header(content_type)
             .status(status)
             .sized_body(Cursor::new(response_body))
             .finalize()),
         Err(_error) => {
             let response = Response::build()
-                .header(ContentType::JsonApi)
+                .header(content_type)
                 .status(status)
                 .sized_body(Cursor::new(response_body))
                 .finalize();
@@ -83,27 +187,83 @@ fn construct_response<'r, Data>(
     }
 }
 
+/// Responds with the document of `response`, with links generated in `context` instead of the
+/// link context of the request
+pub fn respond_in<'r, Data>(
+    response: JsonApiResponse<Data>,
+    context: &LinkContext,
+    request: &Request<'_>,
+) -> Result<Response<'r>, Status>
+where
+    for<'a> JsonApiDocument<'a, Data>: Serialize,
+{
+    let body = serialize(&response, context)?;
+    construct_response(body, response.1, response.0, response_content_type(request))
+}
+
+/// Responds with 201 Created and the document of the created resource, with its `Location`: its
+/// `self` link or its URL in the managed `ResourceRoutes`. Its links are computed only once, for
+/// both the document and the header.
+pub fn respond_created<'r, Data>(data: Data, request: &Request<'_>) -> Result<Response<'r>, Status>
+where
+    Data: Serialize + ResourceIdentifiable + Linkify,
+{
+    let context = link_context(request);
+    let links = data.get_links_in(context);
+    let location = match links.iter().find(|link| link.key() == "self") {
+        Some(link) => Some(link.href().to_string()),
+        None => context.resource_route_url(&data),
+    };
+    let mut body = Vec::with_capacity(DOCUMENT_SIZE_HINT + RESOURCE_SIZE_HINT);
+    serde_json::to_writer(
+        &mut body,
+        &JsonApiResourceDataObject(&data, &links, context),
+    )
+    .map_err(|_e| Status::InternalServerError)?;
+    let mut response = Response::build()
+        .header(response_content_type(request))
+        .status(Status::Created)
+        .sized_body(Cursor::new(body))
+        .finalize();
+    if let Some(location) = location {
+        response.set_header(Header::new("Location", location));
+    }
+    Ok(response)
+}
+
 impl<'r, Data> Responder<'r> for JsonApiResponse<Data>
 where
     Data: Serialize + ResourceIdentifiable + Linkify,
 {
-    default fn respond_to(self, _request: &Request<'_>) -> Result<Response<'r>, Status> {
+    default fn respond_to(self, request: &Request<'_>) -> Result<Response<'r>, Status> {
         // TODO improve or think about what to do in this case...
-        let response = serialize(&self).map_err(|_e| Status::InternalServerError)?;
+        let response = serialize(&self, link_context(request))?;
 
-        construct_response(response, self.1, self.0)
+        construct_response(response, self.1, self.0, response_content_type(request))
     }
 }
 
-impl<'r, Data> Responder<'r> for JsonApiResponse<JsonApiCollection<Data>>
+impl<'r, Data, Links> Responder<'r> for JsonApiResponse<JsonApiCollection<Data, Links>>
 where
     Data: Serialize + ResourceIdentifiable + Linkify,
+    Links: CollectionLinks,
 {
-    fn respond_to(self, _request: &Request<'_>) -> Result<Response<'r>, Status> {
+    fn respond_to(self, request: &Request<'_>) -> Result<Response<'r>, Status> {
         // TODO improve or think about what to do in this case...
-        let response = serialize(&self).map_err(|_e| Status::InternalServerError)?;
+        let response = serialize(&self, link_context(request))?;
+
+        construct_response(response, self.1, self.0, response_content_type(request))
+    }
+}
 
-        construct_response(response, self.1, self.0)
+impl<'r, Data> Responder<'r> for JsonApiResponse<Option<Data>>
+where
+    Data: Serialize + ResourceIdentifiable + Linkify,
+{
+    fn respond_to(self, request: &Request<'_>) -> Result<Response<'r>, Status> {
+        let response = serialize(&self, link_context(request))?;
+
+        construct_response(response, self.1, self.0, response_content_type(request))
     }
 }
 
@@ -200,6 +360,16 @@ mod test_serialize {
         assert_eq!(test_instance_value, test_equals_value);
     }
 
+    #[test]
+    fn serialize_json_api_response_none() {
+        let test_instance_value =
+            serde_json::to_value(JsonApiResponse::<Option<Test>>(Status::Ok, Ok(None))).unwrap();
+        let test_equals_value = json!({
+            "data": null
+        });
+        assert_eq!(test_instance_value, test_equals_value);
+    }
+
     #[test]
     fn serialize_json_api_response_error() {
         let test_error1 = json_api_error!(
@@ -1,5 +1,5 @@
 use crate::ResourceType;
-use serde::de::{MapAccess, Visitor};
+use serde::de::{DeserializeOwned, DeserializeSeed, Expected, IgnoredAny, MapAccess, Visitor};
 use serde::export::fmt::Error;
 use serde::export::Formatter;
 use serde::{Deserialize, Deserializer};
@@ -18,13 +18,147 @@ impl<InputData> Deref for InputDataWrapper<InputData> {
     }
 }
 
+/// Data of the resource object of a POST request, deserialized from the `attributes` of a
+/// resource object of one of the accepted types.
+///
+/// Implemented for every [ResourceType] that implements `Deserialize`, accepting its own type.
+/// Endpoints accepting several types use an enum with a variant for each input type, deriving
+/// `InputResource`:
+///
+/// ```rust
+/// # use rocket_jsonapi::{InputResource, ResourceType};
+/// # use serde::Deserialize;
+/// #[derive(Deserialize, ResourceType)]
+/// #[resource_ident_type = "images"]
+/// struct Image {
+///     url: String,
+/// }
+///
+/// #[derive(Deserialize, ResourceType)]
+/// #[resource_ident_type = "videos"]
+/// struct Video {
+///     url: String,
+///     duration: u32,
+/// }
+///
+/// #[derive(InputResource)]
+/// enum Media {
+///     Image(Image),
+///     Video(Video),
+/// }
+/// ```
+pub trait InputResource: Sized {
+    /// Returns the types of the resource objects that are accepted
+    fn input_types() -> Vec<&'static str>;
+
+    /// Returns true if resource objects of `resource_type` are accepted
+    fn accepts_type(resource_type: &str) -> bool {
+        Self::input_types().contains(&resource_type)
+    }
+
+    /// Deserializes the attributes of a resource object of `resource_type`, which is one of the
+    /// accepted types
+    fn deserialize_input<'de, D>(resource_type: &str, attributes: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>;
+}
+
+impl<InputData> InputResource for InputData
+where
+    InputData: ResourceType + DeserializeOwned,
+{
+    fn input_types() -> Vec<&'static str> {
+        vec![InputData::get_type()]
+    }
+
+    #[inline]
+    fn accepts_type(resource_type: &str) -> bool {
+        InputData::get_type() == resource_type
+    }
+
+    fn deserialize_input<'de, D>(_resource_type: &str, attributes: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        InputData::deserialize(attributes)
+    }
+}
+
+/// Error of a resource object whose `type` is not accepted by `InputData`, listing the accepted
+/// types
+pub fn unaccepted_type<InputData, E>(resource_type: &str) -> E
+where
+    InputData: InputResource,
+    E: serde::de::Error,
+{
+    unaccepted_types(resource_type, &InputData::input_types())
+}
+
+/// Error of a resource object whose `type` is not one of `accepted`
+pub fn unaccepted_types<E>(resource_type: &str, accepted: &[&'static str]) -> E
+where
+    E: serde::de::Error,
+{
+    E::invalid_value(
+        serde::de::Unexpected::Str(resource_type),
+        &AcceptedTypes(accepted),
+    )
+}
+
+struct AcceptedTypes<'a>(&'a [&'static str]);
+
+impl<'a> Expected for AcceptedTypes<'a> {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), Error> {
+        match self.0 {
+            [resource_type] => formatter.write_str(resource_type),
+            resource_types => write!(formatter, "one of {}", resource_types.join(", ")),
+        }
+    }
+}
+
+// Deserializes the attributes of a resource object, once its type is known
+struct InputSeed<'a, InputData> {
+    resource_type: &'a str,
+    marker: PhantomData<InputData>,
+}
+
+impl<'a, 'de, InputData: InputResource> DeserializeSeed<'de> for InputSeed<'a, InputData> {
+    type Value = InputData;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        InputData::deserialize_input(self.resource_type, deserializer)
+    }
+}
+
+impl<'de, InputData> Deserialize<'de> for InputDataWrapper<InputData>
+where
+    InputData: InputResource,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, <D as Deserializer<'de>>::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match InputResourceObject::<InputData>::deserialize(deserializer)?.0 {
+            Ok(input) => Ok(InputDataWrapper(input)),
+            Err(resource_type) => Err(unaccepted_type::<InputData, D::Error>(&resource_type)),
+        }
+    }
+}
+
+/// Resource object of a POST request: its data, or its type if the type is not accepted, so the
+/// conflict can be reported with the `type` as its source
+pub struct InputResourceObject<InputData>(pub Result<InputData, String>);
+
 // Make a Deserialize for a single resource object input type with no user ID
 // How do we make the attributes field deserialize as input type? Try looking at derive output with
 // cargo expand --lib/--bin of some test data, where a field is an owned struct that also has
 // Deserialize derived
-impl<'de, InputData> Deserialize<'de> for InputDataWrapper<InputData>
+impl<'de, InputData> Deserialize<'de> for InputResourceObject<InputData>
 where
-    InputData: ResourceType + Deserialize<'de>,
+    InputData: InputResource,
 {
     fn deserialize<D>(deserializer: D) -> Result<Self, <D as Deserializer<'de>>::Error>
     where
@@ -37,12 +171,18 @@ where
             Type,
             Attributes,
         }
+        // Attributes are deserialized when the type is known, and buffered if they come first
+        enum Attributes<D> {
+            Input(D),
+            Buffered(Value),
+            NotAccepted,
+        }
         struct CreateResourceVisistor<'de, D> {
             marker: PhantomData<D>,
             lifetime: PhantomData<&'de ()>,
         }
-        impl<'de, D: ResourceType + Deserialize<'de>> Visitor<'de> for CreateResourceVisistor<'de, D> {
-            type Value = InputDataWrapper<D>;
+        impl<'de, D: InputResource> Visitor<'de> for CreateResourceVisistor<'de, D> {
+            type Value = InputResourceObject<D>;
 
             fn expecting(&self, formatter: &mut Formatter) -> Result<(), Error> {
                 formatter.write_str("struct InputDataWrapper")
@@ -63,7 +203,7 @@ where
                 A: MapAccess<'de>,
             {
                 let mut resource_type: Option<String> = None;
-                let mut attributes: Option<D> = None;
+                let mut attributes: Option<Attributes<D>> = None;
                 while let Some(key) =
                     match serde::de::MapAccess::next_key::<CreateResourceField>(&mut map) {
                         Ok(val) => val,
@@ -89,11 +229,20 @@ where
                                     "attributes",
                                 ));
                             }
-                            attributes =
-                                Some(match serde::de::MapAccess::next_value::<D>(&mut map) {
-                                    Ok(val) => val,
-                                    Err(err) => return Err(err),
-                                });
+                            attributes = Some(match &resource_type {
+                                Some(resource_type) if D::accepts_type(resource_type) => {
+                                    Attributes::Input(map.next_value_seed(InputSeed {
+                                        resource_type,
+                                        marker: PhantomData,
+                                    })?)
+                                }
+                                // Still read, so malformed documents are reported as such
+                                Some(_) => {
+                                    map.next_value::<IgnoredAny>()?;
+                                    Attributes::NotAccepted
+                                }
+                                None => Attributes::Buffered(map.next_value::<Value>()?),
+                            });
                         }
                     }
                 }
@@ -104,23 +253,24 @@ where
                         Err(err) => return Err(err),
                     },
                 };
+                // Check type field of resource object to see that the given type is accepted
+                if !D::accepts_type(&resource_type) {
+                    return Ok(InputResourceObject(Err(resource_type)));
+                }
                 let attributes = match attributes {
-                    Some(t) => t,
-                    None => match serde::private::de::missing_field("attributes") {
-                        Ok(val) => val,
-                        Err(err) => return Err(err),
-                    },
+                    Some(Attributes::Input(attributes)) => attributes,
+                    Some(Attributes::Buffered(value)) => {
+                        D::deserialize_input(&resource_type, value)
+                            .map_err(<A::Error as serde::de::Error>::custom)?
+                    }
+                    Some(Attributes::NotAccepted) => {
+                        return Ok(InputResourceObject(Err(resource_type)))
+                    }
+                    None => {
+                        return Err(<A::Error as serde::de::Error>::missing_field("attributes"))
+                    }
                 };
-                // Check type field of resource object to see that the given type matches the
-                // desired type
-                let expected_type = D::get_type();
-                if expected_type != resource_type {
-                    return Err(<A::Error as serde::de::Error>::invalid_value(
-                        serde::de::Unexpected::Str(resource_type.as_str()),
-                        &expected_type,
-                    ));
-                }
-                Ok(InputDataWrapper(attributes))
+                Ok(InputResourceObject(Ok(attributes)))
             }
         }
         const FIELDS: &'static [&str] = &["type", "attributes"];
@@ -137,9 +287,9 @@ where
 
 /// Data type representing the deserialized document of a json:api POST request
 #[derive(Deserialize)]
-#[serde(deny_unknown_fields)]
-pub struct JsonApiCreateResource<InputData: ResourceType> {
-    pub data: InputDataWrapper<InputData>,
+#[serde(deny_unknown_fields, bound = "InputData: InputResource")]
+pub struct JsonApiCreateResource<InputData: InputResource> {
+    pub data: InputResourceObject<InputData>,
 }
 
 #[derive(Deserialize)]
@@ -214,11 +364,11 @@ mod test_create_resource {
         let resource_object_test: JsonApiCreateResource<Test> =
             serde_json::from_str(resource_object_json_raw).unwrap();
         assert_eq!(
-            *resource_object_test.data,
-            Test {
+            resource_object_test.data.0,
+            Ok(Test {
                 message: String::from("Hello"),
                 stock: 12
-            }
+            })
         )
     }
 
@@ -241,6 +391,47 @@ mod test_create_resource {
         }
     }
 
+    #[test]
+    fn deserialize_resource_object_attributes_first() {
+        let resource_object_json_raw = r#"
+            {
+                "attributes": {
+                    "message": "Hello",
+                    "stock": 12
+                },
+                "type": "Test"
+            }
+        "#;
+        let resource_object_test: InputDataWrapper<Test> =
+            serde_json::from_str(resource_object_json_raw).unwrap();
+        assert_eq!(
+            *resource_object_test,
+            Test {
+                message: String::from("Hello"),
+                stock: 12
+            }
+        )
+    }
+
+    #[test]
+    fn deserialize_resource_object_invalid_type_message() {
+        let resource_object_json_raw = r#"
+            {
+                "type": "NotTheRightType",
+                "attributes": {
+                    "message": "Hello",
+                    "stock": 12
+                }
+            }
+        "#;
+        let err = serde_json::from_str::<InputDataWrapper<Test>>(resource_object_json_raw)
+            .err()
+            .unwrap();
+        assert!(err
+            .to_string()
+            .starts_with("invalid value: string \"NotTheRightType\", expected Test"));
+    }
+
     #[test]
     fn deserialize_resource_object_invalid_object() {
         let resource_object_json_raw = r#"
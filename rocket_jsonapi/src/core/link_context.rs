@@ -0,0 +1,127 @@
+use crate::links::{LinkConfig, LinkContext, ResourceRoutes};
+use rocket::{Request, State};
+
+const FORWARDED: &str = "Forwarded";
+const HOST: &str = "Host";
+const DEFAULT_SCHEME: &str = "http";
+
+/// Link context of `request`, computed once per request and cached.
+///
+/// The base URL is taken from the managed `LinkConfig` if it has one, otherwise it is built from
+/// the `Forwarded` and `Host` headers. The mount path is the base of the matched route.
+pub fn link_context<'a>(request: &'a Request) -> &'a LinkContext {
+    request.local_cache(|| {
+        let config = request
+            .guard::<State<LinkConfig>>()
+            .succeeded()
+            .map(|config| config.inner());
+        let configured = config.and_then(|config| config.base_url.clone());
+        let base_url = configured.unwrap_or_else(|| {
+            let forwarded = request
+                .headers()
+                .get_one(FORWARDED)
+                .map(forwarded_params)
+                .unwrap_or_default();
+            let host = forwarded
+                .host
+                .or_else(|| request.headers().get_one(HOST).map(String::from));
+            match host {
+                Some(host) => format!(
+                    "{}://{}",
+                    forwarded.proto.as_deref().unwrap_or(DEFAULT_SCHEME),
+                    host
+                ),
+                // Without a host links are relative to the server root
+                None => String::new(),
+            }
+        });
+        let mount_path = request.route().map(|route| route.base()).unwrap_or("");
+        let mut context = LinkContext::new(base_url, mount_path);
+        // The registry is shared, so cloning it into each context is cheap
+        if let Some(routes) = request.guard::<State<ResourceRoutes>>().succeeded() {
+            context = context.with_resource_routes(routes.inner().clone());
+        }
+        match config {
+            Some(config) if config.omit_self_link => context,
+            _ => {
+                // The request URI includes the mount path and query string
+                let self_link = format!("{}{}", context.base_url(), request.uri());
+                context.with_self_link(self_link)
+            }
+        }
+    })
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct ForwardedParams {
+    proto: Option<String>,
+    host: Option<String>,
+}
+
+/// Reads the `proto` and `host` parameters of the first element of a `Forwarded` header, see
+/// [RFC 7239](https://tools.ietf.org/html/rfc7239)
+fn forwarded_params(header: &str) -> ForwardedParams {
+    let mut params = ForwardedParams::default();
+    // The first element was added by the proxy closest to the client
+    let element = header.split(',').next().unwrap_or("");
+    for pair in element.split(';') {
+        let mut pair = pair.splitn(2, '=');
+        let key = pair.next().unwrap_or("").trim();
+        let value = match pair.next() {
+            Some(value) => value.trim().trim_matches('"').to_string(),
+            None => continue,
+        };
+        if key.eq_ignore_ascii_case("proto") {
+            params.proto = Some(value);
+        } else if key.eq_ignore_ascii_case("host") {
+            params.host = Some(value);
+        }
+    }
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::link_context::{forwarded_params, ForwardedParams};
+    use crate::links::LinkContext;
+
+    #[test]
+    fn forwarded_proto_and_host() {
+        assert_eq!(
+            forwarded_params("for=192.0.2.60;proto=https;host=\"example.com:8000\""),
+            ForwardedParams {
+                proto: Some(String::from("https")),
+                host: Some(String::from("example.com:8000")),
+            }
+        );
+    }
+
+    #[test]
+    fn forwarded_first_element() {
+        assert_eq!(
+            forwarded_params("host=example.com, host=proxy.com;proto=https"),
+            ForwardedParams {
+                proto: None,
+                host: Some(String::from("example.com")),
+            }
+        );
+    }
+
+    #[test]
+    fn forwarded_without_params() {
+        assert_eq!(forwarded_params("for=_hidden"), ForwardedParams::default());
+    }
+
+    #[test]
+    fn context_urls() {
+        let context = LinkContext::new("https://example.com/", "/api/");
+        assert_eq!(context.base_url(), "https://example.com");
+        assert_eq!(context.mount_path(), "/api");
+        assert_eq!(
+            context.url("/articles/1"),
+            "https://example.com/api/articles/1"
+        );
+        let root = LinkContext::new("", "/");
+        assert_eq!(root.url("/articles/1"), "/articles/1");
+    }
+}
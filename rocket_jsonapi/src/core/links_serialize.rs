@@ -1,6 +1,7 @@
 use crate::lib::*;
+use serde::ser::SerializeMap;
 
-pub struct LinksSerialize<'a>(pub &'a Vec<Link>);
+pub struct LinksSerialize<'a>(pub &'a Vec<Link<'a>>);
 
 impl Serialize for LinksSerialize<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
@@ -10,17 +11,180 @@ impl Serialize for LinksSerialize<'_> {
         if self.0.len() == 0 {
             return serializer.serialize_none();
         }
-        let mut state = serializer.serialize_struct("LinksSerialize", self.0.len())?;
+        // Keys can be computed at runtime, so the links are serialized as a map
+        let mut state = serializer.serialize_map(Some(self.0.len()))?;
         for link in self.0 {
             match link {
                 Link::Url(key, url) => {
-                    state.serialize_field(key, url)?;
+                    state.serialize_entry(key, url)?;
                 }
                 Link::Object(key, link_object) => {
-                    state.serialize_field(key, link_object)?;
+                    state.serialize_entry(key, link_object)?;
                 }
             }
         }
         state.end()
     }
 }
+
+/// Top-level links of a document, led by a `self` and a `related` link unless the links already
+/// have them
+pub struct TopLevelLinksSerialize<'a>(pub &'a [Link<'a>], pub Option<&'a str>, pub Option<&'a str>);
+
+impl TopLevelLinksSerialize<'_> {
+    fn default_link<'a>(&self, key: &str, link: Option<&'a str>) -> Option<&'a str> {
+        link.filter(|_| !self.0.iter().any(|link| link.key() == key))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty() && self.1.is_none() && self.2.is_none()
+    }
+}
+
+impl Serialize for TopLevelLinksSerialize<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        let self_link = self.default_link("self", self.1);
+        let related_link = self.default_link("related", self.2);
+        let len = self.0.len() + self_link.iter().count() + related_link.iter().count();
+        let mut state = serializer.serialize_map(Some(len))?;
+        if let Some(self_link) = self_link {
+            state.serialize_entry("self", self_link)?;
+        }
+        if let Some(related_link) = related_link {
+            state.serialize_entry("related", related_link)?;
+        }
+        for link in self.0 {
+            match link {
+                Link::Url(key, url) => {
+                    state.serialize_entry(key, url)?;
+                }
+                Link::Object(key, link_object) => {
+                    state.serialize_entry(key, link_object)?;
+                }
+            }
+        }
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::links_serialize::{LinksSerialize, TopLevelLinksSerialize};
+    use crate::links::{Link, LinkObject};
+    use serde::Serialize;
+    use serde_json::json;
+
+    #[derive(Serialize)]
+    struct Meta {
+        count: u32,
+    }
+
+    #[test]
+    fn serialize_links() {
+        let key = format!("page-{}", 2);
+        let links = vec![
+            Link::url("self", "http://example.com/articles"),
+            Link::url(key, "http://example.com/articles?page=2"),
+            Link::object(
+                "describedby",
+                LinkObject::new("http://example.com/schemas/articles"),
+            ),
+        ];
+        assert_eq!(
+            serde_json::to_value(LinksSerialize(&links)).unwrap(),
+            json!({
+                "self": "http://example.com/articles",
+                "page-2": "http://example.com/articles?page=2",
+                "describedby": { "href": "http://example.com/schemas/articles" }
+            })
+        );
+    }
+
+    #[test]
+    fn serialize_link_object_members() {
+        let meta = Meta { count: 10 };
+        let link_object = LinkObject::new("http://example.com/articles/1/comments")
+            .rel("comments")
+            .describedby("http://example.com/schemas/comments")
+            .title("Comments")
+            .media_type("application/vnd.api+json")
+            .hreflang("en")
+            .meta(&meta);
+        assert_eq!(
+            serde_json::to_value(link_object).unwrap(),
+            json!({
+                "href": "http://example.com/articles/1/comments",
+                "rel": "comments",
+                "describedby": "http://example.com/schemas/comments",
+                "title": "Comments",
+                "type": "application/vnd.api+json",
+                "hreflang": "en",
+                "meta": { "count": 10 }
+            })
+        );
+    }
+
+    #[test]
+    fn serialize_link_object_hreflang_list() {
+        let link_object = LinkObject::new("http://example.com")
+            .hreflang("en")
+            .hreflang("da");
+        assert_eq!(
+            serde_json::to_value(link_object).unwrap(),
+            json!({
+                "href": "http://example.com",
+                "hreflang": ["en", "da"]
+            })
+        );
+    }
+
+    #[test]
+    fn serialize_top_level_links_adds_self() {
+        let links = vec![Link::url("next", "http://example.com/articles?page=2")];
+        assert_eq!(
+            serde_json::to_value(TopLevelLinksSerialize(
+                &links,
+                Some("http://example.com/articles"),
+                None
+            ))
+            .unwrap(),
+            json!({
+                "self": "http://example.com/articles",
+                "next": "http://example.com/articles?page=2"
+            })
+        );
+    }
+
+    #[test]
+    fn serialize_top_level_links_keeps_own_self() {
+        let links = vec![Link::url("self", "http://example.com/own")];
+        assert_eq!(
+            serde_json::to_value(TopLevelLinksSerialize(
+                &links,
+                Some("http://example.com/articles"),
+                None
+            ))
+            .unwrap(),
+            json!({ "self": "http://example.com/own" })
+        );
+    }
+
+    #[test]
+    fn serialize_top_level_related_link() {
+        assert_eq!(
+            serde_json::to_value(TopLevelLinksSerialize(
+                &[],
+                Some("http://example.com/articles/1/author"),
+                Some("http://example.com/articles/1")
+            ))
+            .unwrap(),
+            json!({
+                "self": "http://example.com/articles/1/author",
+                "related": "http://example.com/articles/1"
+            })
+        );
+    }
+}
@@ -1,6 +1,12 @@
+pub mod atomic_operations;
 pub mod data_object;
 pub mod general_response;
 pub mod input_data;
+pub mod link_context;
 pub mod links_serialize;
+pub mod negotiation;
+pub mod percent_encoding;
+pub mod relationships_serialize;
+pub mod resource_id;
 pub mod resource_identifier;
 pub mod serialize_no_conversion;
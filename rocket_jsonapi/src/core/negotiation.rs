@@ -0,0 +1,215 @@
+use crate::request::MediaTypeSupport;
+use rocket::http::{ContentType, MediaType, Status};
+use rocket::{Request, State};
+
+const EXT: &str = "ext";
+const PROFILE: &str = "profile";
+// Weight of an `Accept` header entry, it is not a parameter of the media type itself
+const WEIGHT: &str = "q";
+
+/// The `ext` and `profile` parameters of a single JSON:API media type
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MediaTypeParams {
+    pub extensions: Vec<String>,
+    pub profiles: Vec<String>,
+}
+
+impl MediaTypeParams {
+    pub fn is_empty(&self) -> bool {
+        self.extensions.is_empty() && self.profiles.is_empty()
+    }
+
+    /// The JSON:API media type with the `ext` and `profile` parameters applied
+    pub fn content_type(&self) -> ContentType {
+        let mut params = Vec::with_capacity(2);
+        // URI lists are space separated, so they must be quoted
+        if !self.extensions.is_empty() {
+            params.push((EXT, format!("\"{}\"", self.extensions.join(" "))));
+        }
+        if !self.profiles.is_empty() {
+            params.push((PROFILE, format!("\"{}\"", self.profiles.join(" "))));
+        }
+        ContentType::with_params("application", "vnd.api+json", params)
+    }
+}
+
+/// Outcome of negotiating the JSON:API media type of a request.
+///
+/// It is computed once per request and cached, so request guards and responders agree on it.
+pub struct Negotiation {
+    /// What the response may use, negotiated from the `Accept` header
+    pub accept: Result<MediaTypeParams, Status>,
+    /// What the request document uses, `None` if the request has no JSON:API `Content-Type`
+    pub content_type: Option<Result<MediaTypeParams, Status>>,
+}
+
+/// Negotiates the media type of `request` against the `MediaTypeSupport` in managed state, or
+/// against no extensions and profiles if none is managed.
+pub fn negotiate<'a>(request: &'a Request) -> &'a Negotiation {
+    request.local_cache(
+        || match request.guard::<State<MediaTypeSupport>>().succeeded() {
+            Some(support) => negotiate_with(request, support.inner()),
+            None => negotiate_with(request, &MediaTypeSupport::default()),
+        },
+    )
+}
+
+/// Content type of a JSON:API response to `request`, echoing the applied extensions and profiles
+pub fn response_content_type(request: &Request) -> ContentType {
+    match &negotiate(request).accept {
+        Ok(params) if !params.is_empty() => params.content_type(),
+        _ => ContentType::JsonApi,
+    }
+}
+
+fn negotiate_with(request: &Request, support: &MediaTypeSupport) -> Negotiation {
+    Negotiation {
+        accept: negotiate_accept(request, support),
+        content_type: request
+            .content_type()
+            .filter(|content_type| **content_type == ContentType::JsonApi)
+            .map(|content_type| negotiate_content_type(content_type, support)),
+    }
+}
+
+fn negotiate_accept(
+    request: &Request,
+    support: &MediaTypeSupport,
+) -> Result<MediaTypeParams, Status> {
+    // JSON API v. 1.1
+    // Servers MUST ignore instances of the JSON:API media type which are modified by a media type
+    // parameter other than ext or profile. If all instances are modified like that, or every
+    // instance contains at least one unsupported extension, servers MUST respond with a
+    // 406 Not Acceptable status code.
+    request
+        .accept()
+        .iter()
+        .flat_map(|accept| accept.iter())
+        .filter(|query_media_type| query_media_type.0 == MediaType::JsonApi)
+        .filter_map(|query_media_type| {
+            media_type_params(&query_media_type.0, support, true)
+                .map(|params| (query_media_type.weight_or(1.0), params))
+        })
+        // Highest weight wins, the first of equally weighted instances is preferred
+        .fold(
+            None,
+            |best: Option<(f32, MediaTypeParams)>, (weight, params)| match best {
+                Some((best_weight, _)) if best_weight >= weight => best,
+                _ => Some((weight, params)),
+            },
+        )
+        .map(|(_, params)| params)
+        .ok_or(Status::NotAcceptable)
+}
+
+fn negotiate_content_type(
+    content_type: &ContentType,
+    support: &MediaTypeSupport,
+) -> Result<MediaTypeParams, Status> {
+    // JSON API v. 1.1
+    // Servers MUST respond with a 415 Unsupported Media Type status code if a request specifies
+    // the header Content-Type: application/vnd.api+json with any media type parameters other
+    // than ext or profile, or with an unsupported extension.
+    media_type_params(&content_type.0, support, false).ok_or(Status::UnsupportedMediaType)
+}
+
+/// Reads the `ext` and `profile` parameters of a JSON:API media type. Returns `None` if the media
+/// type has any other parameter, or uses an extension that is not supported. Unsupported
+/// profiles are ignored, as the specification requires.
+fn media_type_params(
+    media_type: &MediaType,
+    support: &MediaTypeSupport,
+    allow_weight: bool,
+) -> Option<MediaTypeParams> {
+    let mut params = MediaTypeParams::default();
+    for (key, value) in media_type.params() {
+        if key.eq_ignore_ascii_case(EXT) {
+            for uri in value.split_whitespace() {
+                if !support.supports_extension(uri) {
+                    return None;
+                }
+                params.extensions.push(uri.to_string());
+            }
+        } else if key.eq_ignore_ascii_case(PROFILE) {
+            params.profiles.extend(
+                value
+                    .split_whitespace()
+                    .filter(|uri| support.supports_profile(uri))
+                    .map(String::from),
+            );
+        } else if !(allow_weight && key.eq_ignore_ascii_case(WEIGHT)) {
+            return None;
+        }
+    }
+    Some(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::negotiation::{media_type_params, MediaTypeParams};
+    use crate::request::MediaTypeSupport;
+    use rocket::http::MediaType;
+
+    const ATOMIC: &str = "https://jsonapi.org/ext/atomic";
+    const CURSOR: &str = "https://jsonapi.org/profiles/ethanresnick/cursor-pagination";
+
+    fn support() -> MediaTypeSupport {
+        MediaTypeSupport::new().extension(ATOMIC).profile(CURSOR)
+    }
+
+    fn parse(raw: &str) -> MediaType {
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn params_none() {
+        let params = media_type_params(&parse("application/vnd.api+json"), &support(), false);
+        assert_eq!(params, Some(MediaTypeParams::default()));
+    }
+
+    #[test]
+    fn params_supported_extension_and_profile() {
+        let media_type = parse(&format!(
+            "application/vnd.api+json; ext=\"{}\"; profile=\"{} https://example.com/unknown\"",
+            ATOMIC, CURSOR
+        ));
+        let params = media_type_params(&media_type, &support(), false).unwrap();
+        assert_eq!(params.extensions, vec![ATOMIC.to_string()]);
+        // Unsupported profiles are ignored
+        assert_eq!(params.profiles, vec![CURSOR.to_string()]);
+    }
+
+    #[test]
+    fn params_unsupported_extension() {
+        let media_type = parse("application/vnd.api+json; ext=\"https://example.com/ext\"");
+        assert_eq!(media_type_params(&media_type, &support(), false), None);
+    }
+
+    #[test]
+    fn params_unknown_parameter() {
+        let media_type = parse("application/vnd.api+json; charset=UTF-8");
+        assert_eq!(media_type_params(&media_type, &support(), false), None);
+    }
+
+    #[test]
+    fn params_weight_only_in_accept() {
+        let media_type = parse("application/vnd.api+json; q=0.5");
+        assert_eq!(
+            media_type_params(&media_type, &support(), true),
+            Some(MediaTypeParams::default())
+        );
+        assert_eq!(media_type_params(&media_type, &support(), false), None);
+    }
+
+    #[test]
+    fn params_content_type() {
+        let params = MediaTypeParams {
+            extensions: vec![ATOMIC.to_string()],
+            profiles: vec![],
+        };
+        assert_eq!(
+            params.content_type().to_string(),
+            format!("application/vnd.api+json; ext=\"{}\"", ATOMIC)
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt;
+use std::fmt::Write;
+
+/// Formats a value as a percent-encoded path segment of a URL, see
+/// [RFC 3986](https://tools.ietf.org/html/rfc3986#section-3.3). It is encoded while it is written
+/// to the formatter, so encoding does not allocate.
+pub struct PathSegment<'a, T: ?Sized>(pub &'a T);
+
+impl<T: fmt::Display + ?Sized> fmt::Display for PathSegment<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(PathSegmentWriter(f), "{}", self.0)
+    }
+}
+
+struct PathSegmentWriter<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl Write for PathSegmentWriter<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut start = 0;
+        for (index, byte) in s.bytes().enumerate() {
+            if !is_segment_char(byte) {
+                // Bytes of a multibyte character are all encoded, so `start` is at a character
+                // boundary whenever the run of unencoded characters is not empty
+                if start < index {
+                    self.0.write_str(&s[start..index])?;
+                }
+                write!(self.0, "%{:02X}", byte)?;
+                start = index + 1;
+            }
+        }
+        self.0.write_str(&s[start..])
+    }
+}
+
+// Unreserved characters, sub-delimiters, ':' and '@' may appear unencoded in a path segment
+fn is_segment_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric()
+        || matches!(
+            byte,
+            b'-' | b'.'
+                | b'_'
+                | b'~'
+                | b'!'
+                | b'$'
+                | b'&'
+                | b'\''
+                | b'('
+                | b')'
+                | b'*'
+                | b'+'
+                | b','
+                | b';'
+                | b'='
+                | b':'
+                | b'@'
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::percent_encoding::PathSegment;
+
+    #[test]
+    fn encodes_reserved_characters() {
+        assert_eq!(
+            PathSegment("a b/c?d#e%f").to_string(),
+            "a%20b%2Fc%3Fd%23e%25f"
+        );
+        assert_eq!(PathSegment("ü").to_string(), "%C3%BC");
+    }
+
+    #[test]
+    fn keeps_segment_characters() {
+        assert_eq!(PathSegment(&-12).to_string(), "-12");
+        assert_eq!(
+            PathSegment("a-b.c_d~e:f@g,h").to_string(),
+            "a-b.c_d~e:f@g,h"
+        );
+    }
+}
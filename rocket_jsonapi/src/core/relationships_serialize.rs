@@ -0,0 +1,295 @@
+use crate::lib::*;
+use crate::relationship::{RelationObject, Relationships};
+use serde::ser::SerializeMap;
+
+/// Relationship objects of any resource, none if it does not implement `Relationships`
+pub trait MaybeRelationships {
+    fn relation_objects(&self) -> Vec<RelationObject<'_>>;
+}
+
+impl<T> MaybeRelationships for T {
+    default fn relation_objects(&self) -> Vec<RelationObject<'_>> {
+        vec![]
+    }
+}
+
+impl<T: Relationships> MaybeRelationships for T {
+    fn relation_objects(&self) -> Vec<RelationObject<'_>> {
+        self.get_all_relation_objects()
+    }
+}
+
+/// Serializes relationship objects as the `relationships` member of a resource object. Their
+/// `self` and `related` links are generated from the URL of the resource, if it has one.
+pub struct RelationshipsSerialize<'a>(pub &'a [RelationObject<'a>], pub Option<&'a str>);
+
+impl Serialize for RelationshipsSerialize<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_map(Some(self.0.len()))?;
+        for relation in self.0 {
+            state.serialize_entry(relation.name(), &RelationObjectIn(relation, self.1))?;
+        }
+        state.end()
+    }
+}
+
+/// Serializes a relationship object of the resource at the given URL
+pub struct RelationObjectIn<'a>(pub &'a RelationObject<'a>, pub Option<&'a str>);
+
+impl Serialize for RelationObjectIn<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize_in(self.1, serializer)
+    }
+}
+
+/// Serializes a relationship object as the document of a relationship endpoint, with the URL of
+/// the resource it belongs to and the top-level `self` link of the response
+pub struct RelationDocument<'a>(
+    pub &'a RelationObject<'a>,
+    pub Option<&'a str>,
+    pub Option<&'a str>,
+);
+
+impl Serialize for RelationDocument<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize_document(self.1, self.2, serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::relationships_serialize::RelationshipsSerialize;
+    use crate::links::{Link, Pagination};
+    use crate::relationship::{HaveRelationship, RelationObject, RelationObjectify};
+    use crate::response::JsonApiCollection;
+    use crate::{Linkify, ResourceIdentifiable, ResourceType};
+    use serde::Serialize;
+    use serde_json::json;
+
+    struct Person {
+        id: i32,
+    }
+
+    impl ResourceType for Person {
+        fn get_type() -> &'static str {
+            &"person"
+        }
+    }
+
+    impl ResourceIdentifiable for Person {
+        type IdType = i32;
+
+        fn get_id(&self) -> &Self::IdType {
+            &self.id
+        }
+    }
+
+    impl Linkify for Person {}
+
+    struct Article {
+        author: Option<i32>,
+        comments: Vec<i32>,
+    }
+
+    impl HaveRelationship<'_, Person> for Article {
+        fn get_relation(&self) -> Person {
+            Person { id: 1 }
+        }
+    }
+
+    impl HaveRelationship<'_, Option<Person>> for Article {
+        fn get_relation(&self) -> Option<Person> {
+            self.author.map(|id| Person { id })
+        }
+    }
+
+    #[derive(Serialize)]
+    struct CountMeta {
+        count: usize,
+    }
+
+    impl HaveRelationship<'_, JsonApiCollection<Person>> for Article {
+        fn get_relation(&self) -> JsonApiCollection<Person> {
+            JsonApiCollection::data(self.comments.iter().map(|&id| Person { id }).collect())
+        }
+
+        fn get_relation_meta(&self) -> Option<Box<dyn erased_serde::Serialize>> {
+            Some(Box::new(CountMeta {
+                count: self.comments.len(),
+            }))
+        }
+    }
+
+    fn relationships(article: &Article) -> serde_json::Value {
+        serde_json::to_value(RelationshipsSerialize(
+            &[
+                <Article as RelationObjectify<Person>>::get_relation_object(article, "editor"),
+                <Article as RelationObjectify<Option<Person>>>::get_relation_object(
+                    article, "author",
+                ),
+                <Article as RelationObjectify<JsonApiCollection<Person>>>::get_relation_object(
+                    article, "comments",
+                ),
+            ],
+            None,
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn serialize_relationships() {
+        let article = Article {
+            author: Some(2),
+            comments: vec![3, 4],
+        };
+        assert_eq!(
+            relationships(&article),
+            json!({
+                "editor": { "data": { "id": "1", "type": "person" } },
+                "author": { "data": { "id": "2", "type": "person" } },
+                "comments": {
+                    "data": [
+                        { "id": "3", "type": "person" },
+                        { "id": "4", "type": "person" }
+                    ],
+                    "meta": { "count": 2 }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn serialize_empty_relationships() {
+        let article = Article {
+            author: None,
+            comments: vec![],
+        };
+        assert_eq!(
+            relationships(&article),
+            json!({
+                "editor": { "data": { "id": "1", "type": "person" } },
+                "author": { "data": null },
+                "comments": { "data": [], "meta": { "count": 0 } }
+            })
+        );
+    }
+
+    #[test]
+    fn serialize_links_and_meta_only_relationships() {
+        let article = Article {
+            author: None,
+            comments: vec![3, 4],
+        };
+        let relationships = [
+            RelationObject::links_only("tags", vec![Link::url("related", "/articles/1/tags")]),
+            RelationObject::meta_only("likes", CountMeta { count: 12 }),
+            <Article as RelationObjectify<JsonApiCollection<Person>>>::get_relation_object(
+                &article, "comments",
+            )
+            .without_data()
+            .with_links(vec![Link::url("related", "/articles/1/comments")]),
+        ];
+        assert_eq!(
+            serde_json::to_value(RelationshipsSerialize(&relationships, None)).unwrap(),
+            json!({
+                "tags": {
+                    "links": { "related": "/articles/1/tags" }
+                },
+                "likes": {
+                    "meta": { "count": 12 }
+                },
+                "comments": {
+                    "links": { "related": "/articles/1/comments" },
+                    "meta": { "count": 2 }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn serialize_links_from_resource_url() {
+        let article = Article {
+            author: Some(2),
+            comments: vec![3],
+        };
+        let relationships = [
+            <Article as RelationObjectify<Option<Person>>>::get_relation_object(&article, "author"),
+            <Article as RelationObjectify<JsonApiCollection<Person>>>::get_relation_object(
+                &article, "comments",
+            )
+            .with_links(vec![Link::url("related", "/comments?filter[article]=1")]),
+            RelationObject::meta_only("likes", CountMeta { count: 12 }).without_resource_links(),
+        ];
+        assert_eq!(
+            serde_json::to_value(RelationshipsSerialize(&relationships, Some("/articles/1")))
+                .unwrap(),
+            json!({
+                "author": {
+                    "data": { "id": "2", "type": "person" },
+                    "links": {
+                        "self": "/articles/1/relationships/author",
+                        "related": "/articles/1/author"
+                    }
+                },
+                "comments": {
+                    "data": [{ "id": "3", "type": "person" }],
+                    "links": {
+                        "related": "/comments?filter[article]=1",
+                        "self": "/articles/1/relationships/comments"
+                    },
+                    "meta": { "count": 1 }
+                },
+                "likes": {
+                    "meta": { "count": 12 }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn serialize_paginated_relationship() {
+        struct Feed;
+
+        impl HaveRelationship<'_, JsonApiCollection<Person, Pagination>> for Feed {
+            fn get_relation(&self) -> JsonApiCollection<Person, Pagination> {
+                JsonApiCollection::paginated(
+                    vec![Person { id: 3 }],
+                    Pagination::new()
+                        .first("/feeds/1/relationships/people?page=1")
+                        .next("/feeds/1/relationships/people?page=3")
+                        .prev("/feeds/1/relationships/people?page=1")
+                        .meta(CountMeta { count: 40 }),
+                )
+            }
+        }
+
+        let relationships = [<Feed as RelationObjectify<
+            JsonApiCollection<Person, Pagination>,
+        >>::get_relation_object(&Feed, "people")];
+        assert_eq!(
+            serde_json::to_value(RelationshipsSerialize(&relationships, Some("/feeds/1"))).unwrap(),
+            json!({
+                "people": {
+                    "data": [{ "id": "3", "type": "person" }],
+                    "links": {
+                        "first": "/feeds/1/relationships/people?page=1",
+                        "prev": "/feeds/1/relationships/people?page=1",
+                        "next": "/feeds/1/relationships/people?page=3",
+                        "self": "/feeds/1/relationships/people",
+                        "related": "/feeds/1/people"
+                    },
+                    "meta": { "count": 40 }
+                }
+            })
+        );
+    }
+}
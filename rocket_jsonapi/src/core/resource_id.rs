@@ -0,0 +1,63 @@
+use std::fmt::Write;
+
+/// Escapes a part of a derived `ResourceId` that is followed by a separator, so the separator
+/// only appears between parts. `%` and the characters of the separator are percent-encoded.
+pub fn escape_id_part(part: &str, separator: &str) -> String {
+    let mut escaped = String::with_capacity(part.len());
+    for c in part.chars() {
+        if c == '%' || separator.contains(c) {
+            for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                // Writing to a `String` can't fail
+                let _ = write!(escaped, "%{:02X}", byte);
+            }
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// Reverses `escape_id_part`, `None` if the part has an invalid escape
+pub fn unescape_id_part(part: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(part.len());
+    let mut rest = part.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail
+                .get(..2)
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))?;
+            bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::resource_id::{escape_id_part, unescape_id_part};
+
+    #[test]
+    fn escapes_separator_and_percent() {
+        assert_eq!(escape_id_part("-3", "-"), "%2D3");
+        assert_eq!(escape_id_part("a:b%c", "::"), "a%3Ab%25c");
+        assert_eq!(escape_id_part("a·b", "·"), "a%C2%B7b");
+        assert_eq!(escape_id_part("plain", "-"), "plain");
+    }
+
+    #[test]
+    fn unescapes_escaped_parts() {
+        for part in &["-3", "a:b%c", "a·b", "plain", ""] {
+            for separator in &["-", "::", "·"] {
+                let escaped = escape_id_part(part, separator);
+                assert_eq!(unescape_id_part(&escaped).as_deref(), Some(*part));
+            }
+        }
+        assert_eq!(unescape_id_part("%2"), None);
+        assert_eq!(unescape_id_part("%+1"), None);
+        assert_eq!(unescape_id_part("%FF"), None);
+    }
+}
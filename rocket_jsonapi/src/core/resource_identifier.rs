@@ -1,7 +1,76 @@
+use crate::core::serialize_no_conversion::IdSerialize;
 use crate::lib::*;
-use crate::resource::ResourceType;
+use crate::resource::{InstanceType, ResourceType};
+use std::borrow::Cow;
 use std::marker::PhantomData;
 
+/// Type of a resource: the type of the instance if it implements `InstanceType`, otherwise the
+/// static type
+pub trait MaybeInstanceType {
+    fn type_of(&self) -> &str;
+
+    /// The type without borrowing the resource, only allocating for instance types
+    fn owned_type_of(&self) -> Cow<'static, str>;
+}
+
+impl<T: ResourceType> MaybeInstanceType for T {
+    #[inline]
+    default fn type_of(&self) -> &str {
+        T::get_type()
+    }
+
+    #[inline]
+    default fn owned_type_of(&self) -> Cow<'static, str> {
+        Cow::Borrowed(T::get_type())
+    }
+}
+
+impl<T: InstanceType> MaybeInstanceType for T {
+    fn type_of(&self) -> &str {
+        self.instance_type()
+    }
+
+    fn owned_type_of(&self) -> Cow<'static, str> {
+        Cow::Owned(self.instance_type().to_string())
+    }
+}
+
+/// Implemented by resources with the same id type as `First`. The id of every variant of a
+/// resource enum is returned through it as the id type of the first variant, which is the id type
+/// of the enum.
+pub trait SameIdType<First: ResourceIdentifiable> {
+    fn first_id(&self) -> &First::IdType;
+}
+
+impl<First, R> SameIdType<First> for R
+where
+    First: ResourceIdentifiable,
+    R: ResourceIdentifiable,
+    R::IdType: IdTypeOf<First::IdType>,
+{
+    #[inline]
+    fn first_id(&self) -> &First::IdType {
+        self.get_id().as_id_type()
+    }
+}
+
+// Implemented by id types for themselves only, fails with the error of variants with an id type
+// that differs from the one of the first variant
+#[rustc_on_unimplemented(
+    message = "all variants of a resource enum must have the same id type",
+    label = "the variant has the id type `{Self}` instead of `{IdType}` like the first variant"
+)]
+pub trait IdTypeOf<IdType> {
+    fn as_id_type(&self) -> &IdType;
+}
+
+impl<T> IdTypeOf<T> for T {
+    #[inline]
+    fn as_id_type(&self) -> &T {
+        self
+    }
+}
+
 /// Object to represent a "resource identifier object", which is an object that identifies an
 /// individual resource. [See specification](https://jsonapi
 /// .org/format/#document-resource-identifier-objects).
@@ -12,7 +81,7 @@ use std::marker::PhantomData;
 pub struct ResourceIdentifierObject<Data> {
     pub id: String,
     #[serde(rename = "type")]
-    pub object_type: &'static str,
+    pub object_type: Cow<'static, str>,
     phantom: PhantomData<Data>,
 }
 
@@ -22,7 +91,7 @@ impl<Data> ResourceIdentifierObject<Data> {
     pub fn create_identifier<T: ResourceIdentifiable>(resource: &T) -> Self {
         ResourceIdentifierObject::<Data> {
             id: resource.get_id().to_string(),
-            object_type: T::get_type(),
+            object_type: resource.owned_type_of(),
             phantom: PhantomData,
         }
     }
@@ -32,7 +101,7 @@ impl<Data, T: ResourceIdentifiable> From<&T> for ResourceIdentifierObject<Data>
     fn from(resource: &T) -> Self {
         ResourceIdentifierObject::<Data> {
             id: resource.get_id().to_string(),
-            object_type: T::get_type(),
+            object_type: resource.owned_type_of(),
             phantom: PhantomData,
         }
     }
@@ -47,6 +116,15 @@ where
     }
 }
 
+impl<Data> InstanceType for ResourceIdentifierObject<Data>
+where
+    Data: ResourceType,
+{
+    fn instance_type(&self) -> &str {
+        &self.object_type
+    }
+}
+
 impl<Data> ResourceIdentifiable for ResourceIdentifierObject<Data>
 where
     Data: ResourceType,
@@ -79,8 +157,8 @@ where
         S: Serializer,
     {
         let mut state = serializer.serialize_struct("ResourceIdentifierObject", 3)?;
-        state.serialize_field("id", &self.0.get_id().to_string())?;
-        state.serialize_field("type", R::get_type())?;
+        state.serialize_field("id", &IdSerialize(self.0.get_id()))?;
+        state.serialize_field("type", self.0.type_of())?;
         state.end()
     }
 }
@@ -1,3 +1,6 @@
+use crate::lib::*;
+use std::fmt;
+
 pub trait CanSerializeNoConversion {
     fn as_str(&self) -> &str;
 }
@@ -43,3 +46,76 @@ impl CanSerializeNoConversion for Box<&String> {
         self
     }
 }
+
+/// Formats a resource id as the string `id` of a resource object and in links. Ids that are
+/// strings already are written as they are, and integer ids are formatted on the stack, so only
+/// other id types allocate a `String` through `ToString`.
+pub struct IdSerialize<'a, T>(pub &'a T);
+
+impl<'a, T: ToString> fmt::Display for IdSerialize<'a, T> {
+    default fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0.to_string())
+    }
+}
+
+impl<'a, T: ToString + CanSerializeNoConversion> fmt::Display for IdSerialize<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0.as_str())
+    }
+}
+
+macro_rules! impl_id_serialize_for_integers {
+    ($($id_type:ty),*) => {
+        $(
+            impl<'a> fmt::Display for IdSerialize<'a, $id_type> {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str(itoa::Buffer::new().format(*self.0))
+                }
+            }
+        )*
+    };
+}
+
+impl_id_serialize_for_integers!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+// `collect_str` writes straight to the output of serializers like `serde_json::Serializer`
+impl<'a, T: ToString> Serialize for IdSerialize<'a, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::serialize_no_conversion::IdSerialize;
+    use serde_json::json;
+
+    #[test]
+    fn integer_ids_serialize_as_strings() {
+        assert_eq!(
+            serde_json::to_value(IdSerialize(&42i32)).unwrap(),
+            json!("42")
+        );
+        assert_eq!(
+            serde_json::to_value(IdSerialize(&-7i64)).unwrap(),
+            json!("-7")
+        );
+        assert_eq!(
+            serde_json::to_value(IdSerialize(&u128::MAX)).unwrap(),
+            json!(u128::MAX.to_string())
+        );
+    }
+
+    #[test]
+    fn other_ids_serialize_as_strings() {
+        assert_eq!(
+            serde_json::to_value(IdSerialize(&"abc".to_string())).unwrap(),
+            json!("abc")
+        );
+        assert_eq!(serde_json::to_value(IdSerialize(&'x')).unwrap(), json!("x"));
+        assert_eq!(format!("/articles/{}", IdSerialize(&12u8)), "/articles/12");
+    }
+}
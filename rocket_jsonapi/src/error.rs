@@ -137,11 +137,39 @@ pub struct JsonApiError {
     pub title: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub detail: Option<String>,
-    // TODO source
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<JsonApiErrorSource>,
     // TODO links
     // TODO meta
 }
 
+/// Struct representing the `source` member of a JSON:API error, pointing to the cause of the error
+///
+/// [See specification](https://jsonapi.org/format/#error-objects)
+#[derive(Debug, PartialEq, Default, Serialize)]
+pub struct JsonApiErrorSource {
+    /// A [JSON Pointer](https://tools.ietf.org/html/rfc6901) to the value in the request
+    /// document that caused the error, e.g. `"/data/attributes/title"`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<String>,
+    /// Name of the URI query parameter that caused the error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameter: Option<String>,
+    /// Name of the request header that caused the error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<String>,
+}
+
+impl JsonApiErrorSource {
+    /// Constructs a source pointing to `pointer` in the request document
+    pub fn pointer<P: Into<String>>(pointer: P) -> Self {
+        JsonApiErrorSource {
+            pointer: Some(pointer.into()),
+            ..Default::default()
+        }
+    }
+}
+
 /// Takes a variable set of field assignments, and initializes an instance of `JsonApiError`
 ///
 /// ## Example
@@ -1,6 +1,8 @@
+#![allow(incomplete_features)]
 #![feature(specialization)]
 #![feature(associated_type_defaults)]
 #![feature(associated_type_bounds)]
+#![feature(rustc_attrs)]
 
 //! # JSON:API + Rocket.rs
 //!
@@ -26,12 +28,36 @@ mod lib {
     // Possible to include core modules if we want them globally
 }
 
+// Implements a trait for the smart pointers resources are held in, like a cache, forwarding to
+// the resource through `**self`
+macro_rules! impl_for_pointers {
+    (impl<T: $bound:path> $trait:path { $($body:tt)* }) => {
+        impl<T: $bound> $trait for Box<T> {
+            $($body)*
+        }
+
+        impl<T: $bound> $trait for std::rc::Rc<T> {
+            $($body)*
+        }
+
+        impl<T: $bound> $trait for std::sync::Arc<T> {
+            $($body)*
+        }
+
+        impl<'a, T: $bound + ToOwned> $trait for std::borrow::Cow<'a, T> {
+            $($body)*
+        }
+    };
+}
+
 pub mod info;
 pub mod resource;
 pub mod response;
+pub use resource::ResourceId;
 pub use resource::ResourceIdentifiable;
 pub use resource::ResourceType;
 pub mod request;
+pub use request::InputResource;
 
 // Exposing Linkify on root level path, because macros can only be on root level
 pub mod links;
@@ -41,6 +67,22 @@ pub mod relationship;
 
 pub mod error;
 
+pub mod atomic;
+
+// Used by the code generated by the derive macros
+#[doc(hidden)]
+pub mod export {
+    pub use crate::core::data_object::{ForwardResourceMeta, MaybeResourceMeta};
+    pub use crate::core::input_data::unaccepted_type;
+    pub use crate::core::percent_encoding::PathSegment;
+    pub use crate::core::relationships_serialize::MaybeRelationships;
+    pub use crate::core::resource_id::{escape_id_part, unescape_id_part};
+    pub use crate::core::resource_identifier::{MaybeInstanceType, SameIdType};
+    pub use crate::core::serialize_no_conversion::IdSerialize;
+    pub use serde::ser::SerializeStruct;
+    pub use serde::{Deserializer, Serialize, Serializer};
+}
+
 #[cfg(feature = "rocket_jsonapi_derive")]
 #[allow(unused_imports)]
 #[macro_use]
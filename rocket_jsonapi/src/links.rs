@@ -1,33 +1,501 @@
 //! # Responding with link metadata
+use crate::core::link_context::link_context;
+use crate::core::percent_encoding::PathSegment;
+use crate::core::resource_identifier::MaybeInstanceType;
+use crate::core::serialize_no_conversion::IdSerialize;
 use crate::lib::*;
+use crate::resource::ResourceType;
 use erased_serde::Serialize as RefSerialize;
+use rocket::http::uri::Origin;
+use rocket::request::{self, FromRequest};
+use rocket::Request;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
 
-type Key = &'static str;
+/// Key of a link in a links object, can be static or computed at runtime
+pub type Key = Cow<'static, str>;
 type Url = String;
 
-#[derive(Serialize)]
-pub struct LinkObject {
+/// A link object, as defined by the
+/// [specification](https://jsonapi.org/format/#document-links-link-object)
+///
+/// Only `href` is required, the other members are set with the builder methods. The `meta` is
+/// borrowed, e.g. from the resource the link belongs to:
+///
+/// ```rust
+/// # use rocket_jsonapi::links::LinkObject;
+/// # use serde::Serialize;
+/// #[derive(Serialize)]
+/// struct Meta {
+///     count: u32,
+/// }
+///
+/// let meta = Meta { count: 10 };
+/// let link = LinkObject::new("http://example.com/articles/1/comments")
+///     .title("Comments")
+///     .hreflang("en")
+///     .meta(&meta);
+/// ```
+#[derive(Default, Serialize)]
+pub struct LinkObject<'a> {
     pub href: Url,
-    pub meta: Box<dyn RefSerialize>,
+    /// The link's relation type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rel: Option<String>,
+    /// Link to a description document for the link target
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub describedby: Option<Url>,
+    /// Human-readable label of the link destination
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Media type of the link's target
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    /// Languages of the link's target, serialized as a string if there is only one
+    #[serde(
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_hreflang"
+    )]
+    pub hreflang: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<&'a dyn RefSerialize>,
 }
 
-impl LinkObject {
-    pub fn new(href: Url, meta: Box<dyn RefSerialize>) -> Self {
-        LinkObject { href, meta }
+impl<'a> LinkObject<'a> {
+    /// Constructs a link object with only `href`
+    pub fn new<U: Into<Url>>(href: U) -> Self {
+        LinkObject {
+            href: href.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn rel<R: Into<String>>(mut self, rel: R) -> Self {
+        self.rel = Some(rel.into());
+        self
+    }
+
+    pub fn describedby<U: Into<Url>>(mut self, describedby: U) -> Self {
+        self.describedby = Some(describedby.into());
+        self
+    }
+
+    pub fn title<T: Into<String>>(mut self, title: T) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn media_type<T: Into<String>>(mut self, media_type: T) -> Self {
+        self.media_type = Some(media_type.into());
+        self
+    }
+
+    /// Adds a language of the link's target
+    pub fn hreflang<L: Into<String>>(mut self, hreflang: L) -> Self {
+        self.hreflang.push(hreflang.into());
+        self
+    }
+
+    pub fn meta<M: Serialize>(mut self, meta: &'a M) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+}
+
+fn serialize_hreflang<S>(hreflang: &[String], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match hreflang {
+        [hreflang] => serializer.serialize_str(hreflang),
+        hreflang => hreflang.serialize(serializer),
     }
 }
 
-pub enum Link {
+/// A link of a links object, as a plain URL or a link object. Link objects can borrow their
+/// `meta` for the lifetime `'a`.
+pub enum Link<'a> {
     Url(Key, Url),
-    Object(Key, LinkObject),
+    Object(Key, LinkObject<'a>),
+}
+
+impl<'a> Link<'a> {
+    /// Constructs a link that serializes as a plain URL
+    pub fn url<K: Into<Key>, U: Into<Url>>(key: K, href: U) -> Self {
+        Link::Url(key.into(), href.into())
+    }
+
+    /// Constructs a link that serializes as a link object
+    pub fn object<K: Into<Key>>(key: K, link_object: LinkObject<'a>) -> Self {
+        Link::Object(key.into(), link_object)
+    }
+
+    pub fn key(&self) -> &str {
+        match self {
+            Link::Url(key, _) | Link::Object(key, _) => key,
+        }
+    }
+
+    pub fn href(&self) -> &str {
+        match self {
+            Link::Url(_, href) => href,
+            Link::Object(_, link_object) => &link_object.href,
+        }
+    }
+
+    /// Takes the URL out of the link
+    pub fn into_href(self) -> Url {
+        match self {
+            Link::Url(_, href) => href,
+            Link::Object(_, link_object) => link_object.href,
+        }
+    }
 }
 
 // TODO derive version? Maybe for the simple URL case
 // TODO make return type an Option? Or own enum?
 pub trait Linkify {
-    fn get_links(&self) -> Vec<Link> {
+    fn get_links(&self) -> Vec<Link<'_>> {
         vec![]
     }
+
+    /// Links of the resource when responding to a request, with access to the request's base URL
+    /// and mount path through `context`. Defaults to `get_links`.
+    ///
+    /// ```rust
+    /// # #![feature(proc_macro_hygiene, decl_macro)]
+    /// # #[macro_use]
+    /// # extern crate rocket;
+    /// # use rocket_jsonapi::links::{Link, LinkContext, Linkify};
+    /// struct Article {
+    ///     id: i32,
+    /// }
+    ///
+    /// #[get("/articles/<id>")]
+    /// fn article(id: i32) {}
+    ///
+    /// impl Linkify for Article {
+    ///     fn get_links_in(&self, context: &LinkContext) -> Vec<Link> {
+    ///         vec![Link::url("self", context.uri(&uri!(article: self.id)))]
+    ///     }
+    /// }
+    /// # fn main() {}
+    /// ```
+    fn get_links_in(&self, _context: &LinkContext) -> Vec<Link<'_>> {
+        self.get_links()
+    }
+
+    /// URL of the `self` link of the resource in `context`, used to generate the links of its
+    /// relationships. Defaults to searching `get_links_in`, implement it to skip building the
+    /// other links of every resource in large collections.
+    fn self_link_in(&self, context: &LinkContext) -> Option<Url> {
+        self.get_links_in(context)
+            .into_iter()
+            .find(|link| link.key() == "self")
+            .map(Link::into_href)
+    }
+}
+
+impl<T: Linkify> Linkify for &T {
+    fn get_links(&self) -> Vec<Link<'_>> {
+        (**self).get_links()
+    }
+
+    fn get_links_in(&self, context: &LinkContext) -> Vec<Link<'_>> {
+        (**self).get_links_in(context)
+    }
+
+    fn self_link_in(&self, context: &LinkContext) -> Option<Url> {
+        (**self).self_link_in(context)
+    }
+}
+
+impl_for_pointers! {
+    impl<T: Linkify> Linkify {
+        fn get_links(&self) -> Vec<Link<'_>> {
+            (**self).get_links()
+        }
+
+        fn get_links_in(&self, context: &LinkContext) -> Vec<Link<'_>> {
+            (**self).get_links_in(context)
+        }
+
+        fn self_link_in(&self, context: &LinkContext) -> Option<Url> {
+            (**self).self_link_in(context)
+        }
+    }
+}
+
+/// Configuration of link generation, manage an instance of it to apply it:
+///
+/// ```rust
+/// # use rocket_jsonapi::links::LinkConfig;
+/// rocket::ignite().manage(LinkConfig::new().base_url("https://api.example.com"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LinkConfig {
+    pub(crate) base_url: Option<String>,
+    pub(crate) omit_self_link: bool,
+}
+
+impl LinkConfig {
+    pub fn new() -> Self {
+        LinkConfig::default()
+    }
+
+    /// Sets the scheme and host links are built with, instead of reading them from the `Host` and
+    /// `Forwarded` headers of each request
+    pub fn base_url<B: Into<String>>(mut self, base_url: B) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Stops responses from getting a top-level `self` link to the request URI by default
+    pub fn without_self_link(mut self) -> Self {
+        self.omit_self_link = true;
+        self
+    }
+}
+
+/// Where the links of a response point to: the base URL of the server and the path the current
+/// route is mounted at.
+///
+/// The base URL comes from `LinkConfig` if it is managed, otherwise from the `Forwarded` or
+/// `Host` header of the request. Without any of them, the base URL is empty and links are
+/// relative to the server root. Also usable as a request guard.
+///
+/// When responding to a request, the context also holds the URL of the request, which is used as
+/// the top-level `self` link of the response unless `Linkify` or `JsonApiCollection` provide one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinkContext {
+    base_url: String,
+    mount_path: String,
+    self_link: Option<String>,
+    related_link: Option<String>,
+    resource_routes: Option<ResourceRoutes>,
+}
+
+impl LinkContext {
+    pub fn new<B: Into<String>, M: Into<String>>(base_url: B, mount_path: M) -> Self {
+        let mut base_url = base_url.into();
+        let mut mount_path = mount_path.into();
+        // Paths are appended to both, so trailing slashes would double up
+        while base_url.ends_with('/') {
+            base_url.pop();
+        }
+        while mount_path.ends_with('/') {
+            mount_path.pop();
+        }
+        LinkContext {
+            base_url,
+            mount_path,
+            self_link: None,
+            related_link: None,
+            resource_routes: None,
+        }
+    }
+
+    /// Sets the URL used as top-level `self` link
+    pub fn with_self_link<U: Into<String>>(mut self, self_link: U) -> Self {
+        self.self_link = Some(self_link.into());
+        self
+    }
+
+    /// URL used as top-level `self` link, the request URI including its query string
+    pub fn self_link(&self) -> Option<&str> {
+        self.self_link.as_deref()
+    }
+
+    /// Sets the URL used as top-level `related` link, e.g. the resource a related resource
+    /// endpoint is reached from
+    pub fn with_related_link<U: Into<String>>(mut self, related_link: U) -> Self {
+        self.related_link = Some(related_link.into());
+        self
+    }
+
+    /// URL used as top-level `related` link
+    pub fn related_link(&self) -> Option<&str> {
+        self.related_link.as_deref()
+    }
+
+    /// Sets the registry used to link to resources that do not provide a `self` link
+    pub fn with_resource_routes(mut self, resource_routes: ResourceRoutes) -> Self {
+        self.resource_routes = Some(resource_routes);
+        self
+    }
+
+    /// URL of `resource`: its `self` link from `Linkify`, or its path in the `ResourceRoutes` of
+    /// the context
+    pub fn resource_url<R>(&self, resource: &R) -> Option<String>
+    where
+        R: ResourceIdentifiable + Linkify,
+    {
+        resource
+            .self_link_in(self)
+            .or_else(|| self.resource_route_url(resource))
+    }
+
+    /// URL of `resource` from its path in the `ResourceRoutes` of the context, with its id
+    /// percent-encoded
+    pub fn resource_route_url<R: ResourceIdentifiable>(&self, resource: &R) -> Option<String> {
+        self.resource_routes.as_ref()?.resource_url(
+            &self.base_url,
+            resource.type_of(),
+            IdSerialize(resource.get_id()),
+        )
+    }
+
+    /// Scheme and host, e.g. `https://example.com`, without a trailing slash
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Path the current route is mounted at, without a trailing slash
+    pub fn mount_path(&self) -> &str {
+        &self.mount_path
+    }
+
+    /// URL of `path` relative to the mount path of the current route
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}{}", self.base_url, self.mount_path, path)
+    }
+
+    /// URL of a route mounted with the current route, from the output of `uri!(route: ...)`
+    pub fn uri(&self, origin: &Origin) -> String {
+        self.url(&origin.to_string())
+    }
+
+    /// URL of a path from the server root, e.g. the output of `uri!("/mount", route: ...)` for
+    /// routes mounted elsewhere
+    pub fn absolute(&self, origin: &Origin) -> String {
+        format!("{}{}", self.base_url, origin)
+    }
+}
+
+/// Pagination of a collection, given to `JsonApiCollection::paginated`.
+///
+/// The `first`, `prev`, `next` and `last` links, and the `meta`, come out as top-level members of
+/// a collection response, and inside the relationship object of a to-many relationship.
+///
+/// ```rust
+/// # use rocket_jsonapi::links::Pagination;
+/// # use serde::Serialize;
+/// #[derive(Serialize)]
+/// struct PageMeta {
+///     total: u64,
+/// }
+///
+/// let pagination = Pagination::new()
+///     .first("/articles/1/comments?page[number]=1")
+///     .next("/articles/1/comments?page[number]=3")
+///     .prev("/articles/1/comments?page[number]=1")
+///     .meta(PageMeta { total: 1024 });
+/// ```
+#[derive(Default)]
+pub struct Pagination {
+    pub(crate) links: Vec<Link<'static>>,
+    pub(crate) meta: Option<Box<dyn RefSerialize>>,
+}
+
+impl Pagination {
+    pub fn new() -> Self {
+        Pagination::default()
+    }
+
+    pub fn first<U: Into<Url>>(self, first: U) -> Self {
+        self.link("first", first.into())
+    }
+
+    pub fn prev<U: Into<Url>>(self, prev: U) -> Self {
+        self.link("prev", prev.into())
+    }
+
+    pub fn next<U: Into<Url>>(self, next: U) -> Self {
+        self.link("next", next.into())
+    }
+
+    pub fn last<U: Into<Url>>(self, last: U) -> Self {
+        self.link("last", last.into())
+    }
+
+    fn link(mut self, key: &'static str, url: Url) -> Self {
+        self.links.retain(|link| link.key() != key);
+        self.links.push(Link::url(key, url));
+        self
+    }
+
+    /// Sets meta information of the page, e.g. the total number of resources
+    pub fn meta<M: Serialize + 'static>(mut self, meta: M) -> Self {
+        self.meta = Some(Box::new(meta));
+        self
+    }
+
+    /// Splits the pagination into its links and its meta
+    pub fn into_parts(self) -> (Vec<Link<'static>>, Option<Box<dyn RefSerialize>>) {
+        (self.links, self.meta)
+    }
+}
+
+/// Registry of where resources are served, used to link to resources that do not provide a
+/// `self` link through `Linkify`, e.g. for the `Location` header of 201 Created responses and the
+/// links of relationships.
+///
+/// ```rust
+/// # use rocket_jsonapi::links::ResourceRoutes;
+/// # use rocket_jsonapi::ResourceType;
+/// # struct Article;
+/// # impl ResourceType for Article {
+/// #     fn get_type() -> &'static str { "articles" }
+/// # }
+/// // Articles are served at /api/articles/<id>
+/// let resource_routes = ResourceRoutes::new().resource::<Article>("/api/articles");
+/// assert_eq!(
+///     resource_routes.resource_path("articles", "a/b"),
+///     Some("/api/articles/a%2Fb".to_string())
+/// );
+/// rocket::ignite().manage(resource_routes);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResourceRoutes(Arc<HashMap<&'static str, String>>);
+
+impl ResourceRoutes {
+    pub fn new() -> Self {
+        ResourceRoutes::default()
+    }
+
+    /// Registers the path of the collection of `R` resources from the server root. The path of a
+    /// single resource is the collection path followed by its id.
+    pub fn resource<R: ResourceType>(mut self, path: &str) -> Self {
+        Arc::make_mut(&mut self.0).insert(R::get_type(), path.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Path of the resource of type `resource_type` with id `id` percent-encoded, if the type is
+    /// registered
+    pub fn resource_path<I: fmt::Display>(&self, resource_type: &str, id: I) -> Option<String> {
+        self.resource_url("", resource_type, id)
+    }
+
+    // The path prefixed with `base_url`, formatted at once so links need a single allocation
+    fn resource_url<I: fmt::Display>(
+        &self,
+        base_url: &str,
+        resource_type: &str,
+        id: I,
+    ) -> Option<String> {
+        let path = self.0.get(resource_type)?;
+        Some(format!("{}{}/{}", base_url, path, PathSegment(&id)))
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for LinkContext {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(link_context(request).clone())
+    }
 }
 
 // TODO maybe do general implementation returning empty list?
@@ -1,7 +1,10 @@
 //! # Responding with relationship metadata
-use crate::core::resource_identifier::ResourceIdentifierObject;
+use crate::core::resource_identifier::{MaybeInstanceType, ResourceIdentifierObject};
 use crate::lib::*;
-use crate::response::JsonApiCollection;
+use crate::response::{CollectionLinks, JsonApiCollection};
+use erased_serde::Serialize as RefSerialize;
+use serde::ser::SerializeMap;
+use std::borrow::Cow;
 
 //pub type Relationship = Box<dyn ResourceIdentifiable>;
 //pub type Relationships = Vec<Relationship>;
@@ -10,7 +13,7 @@ use crate::response::JsonApiCollection;
 struct ResIdenObjNonGeneric {
     pub id: String,
     #[serde(rename = "type")]
-    pub object_type: &'static str,
+    pub object_type: Cow<'static, str>,
 }
 
 impl<To> From<ResourceIdentifierObject<To>> for ResIdenObjNonGeneric
@@ -19,9 +22,8 @@ where
 {
     fn from(res: ResourceIdentifierObject<To>) -> Self {
         ResIdenObjNonGeneric {
-            // TODO clone needed?
-            id: res.get_id().to_string(),
-            object_type: To::get_type(),
+            id: res.id,
+            object_type: res.object_type,
         }
     }
 }
@@ -34,59 +36,398 @@ where
         ResIdenObjNonGeneric {
             // TODO clone needed?
             id: data.get_id().to_string(),
-            object_type: Data::get_type(),
+            object_type: data.owned_type_of(),
+        }
+    }
+}
+
+/// Resource linkage of a relationship. To-one and to-many relationships are kept apart, so an
+/// empty to-one relationship serializes as `null` and an empty to-many relationship as `[]`.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum RelationData {
+    ToOne(Option<ResIdenObjNonGeneric>),
+    ToMany(Vec<ResIdenObjNonGeneric>),
+}
+
+/// A relationship object, serialized as a member of the `relationships` of a resource object.
+///
+/// [See specification](https://jsonapi.org/format/#document-resource-object-relationships)
+///
+/// A relationship object contains any mix of resource linkage (`data`), `links` and `meta`. Large
+/// to-many relationships can leave out `data` and only be exposed through a `related` link:
+///
+/// ```rust
+/// # use rocket_jsonapi::links::Link;
+/// # use rocket_jsonapi::relationship::RelationObject;
+/// # use serde::Serialize;
+/// #[derive(Serialize)]
+/// struct CommentsMeta {
+///     count: u64,
+/// }
+///
+/// let comments = RelationObject::links_only(
+///     "comments",
+///     vec![Link::url("related", "/articles/1/comments")],
+/// )
+/// .with_meta(CommentsMeta { count: 1024 });
+/// ```
+pub struct RelationObject<'a> {
+    name: &'static str,
+    data: Option<RelationData>,
+    links: Vec<Link<'a>>,
+    meta: Option<Box<dyn RefSerialize>>,
+    resource_links: bool,
+}
+
+impl<'a> RelationObject<'a> {
+    /// Constructs a relationship object with only `links`
+    pub fn links_only(name: &'static str, links: Vec<Link<'a>>) -> Self {
+        RelationObject {
+            name,
+            data: None,
+            links,
+            meta: None,
+            resource_links: true,
+        }
+    }
+
+    /// Constructs a relationship object with only `meta`
+    pub fn meta_only<M: Serialize + 'static>(name: &'static str, meta: M) -> Self {
+        RelationObject::links_only(name, vec![]).with_meta(meta)
+    }
+
+    /// Sets the `meta` of the relationship, e.g. the number of related resources
+    pub fn with_meta<M: Serialize + 'static>(mut self, meta: M) -> Self {
+        self.meta = Some(Box::new(meta));
+        self
+    }
+
+    /// Adds `links` to the links of the relationship
+    pub fn with_links(mut self, mut links: Vec<Link<'a>>) -> Self {
+        self.links.append(&mut links);
+        self
+    }
+
+    /// Leaves out the resource linkage, so the relationship is exposed through its links and
+    /// meta only
+    pub fn without_data(mut self) -> Self {
+        self.data = None;
+        self
+    }
+
+    /// Stops the `self` and `related` links of the relationship from being generated from the URL
+    /// of the resource it belongs to
+    pub fn without_resource_links(mut self) -> Self {
+        self.resource_links = false;
+        self
+    }
+
+    /// Name of the relationship, its key in the `relationships` object
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns true if the relationship has resource linkage for a to-many relationship
+    pub fn is_to_many(&self) -> bool {
+        matches!(self.data, Some(RelationData::ToMany(_)))
+    }
+}
+
+// Relationship objects of relations borrowed from fields of the resource, used by
+// `#[derive(Relationships)]`
+impl RelationObject<'_> {
+    #[doc(hidden)]
+    pub fn to_one<To>(name: &'static str, to: Option<&To>) -> Self
+    where
+        To: ResourceIdentifiable,
+    {
+        RelationObject {
+            name,
+            data: Some(RelationData::ToOne(to.map(|to| to.into()))),
+            links: vec![],
+            meta: None,
+            resource_links: true,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn to_many<To>(name: &'static str, to: &[To]) -> Self
+    where
+        To: ResourceIdentifiable,
+    {
+        RelationObject {
+            name,
+            data: Some(RelationData::ToMany(
+                to.iter().map(|to| to.into()).collect(),
+            )),
+            links: vec![],
+            meta: None,
+            resource_links: true,
+        }
+    }
+}
+
+impl RelationObject<'_> {
+    /// Whether the link `key` is generated from the URL of the resource the relationship belongs
+    /// to. Links with the same key given to the relationship take precedence.
+    fn generates_link(&self, key: &str) -> bool {
+        self.resource_links && !self.has_link(key)
+    }
+
+    fn has_link(&self, key: &str) -> bool {
+        self.links.iter().any(|link| link.key() == key)
+    }
+
+    /// Serializes the relationship object of a resource at `resource_url`, see `generates_link`
+    pub(crate) fn serialize_in<S>(
+        &self,
+        resource_url: Option<&str>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let links = RelationLinks {
+            relation: self,
+            self_url: resource_url.filter(|_| self.generates_link("self")),
+            self_link: None,
+            related_url: resource_url.filter(|_| self.generates_link("related")),
+        };
+        self.serialize_with_links(links, serializer)
+    }
+
+    /// Serializes the relationship object as the document of a relationship endpoint. Its `self`
+    /// link is `self_link`, the request URI, unless the relationship has one.
+    pub(crate) fn serialize_document<S>(
+        &self,
+        resource_url: Option<&str>,
+        self_link: Option<&str>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let links = RelationLinks {
+            relation: self,
+            self_url: None,
+            self_link: self_link.filter(|_| !self.has_link("self")),
+            related_url: resource_url.filter(|_| self.generates_link("related")),
+        };
+        self.serialize_with_links(links, serializer)
+    }
+
+    fn serialize_with_links<S>(
+        &self,
+        links: RelationLinks,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("RelationObject", 3)?;
+        if let Some(data) = &self.data {
+            state.serialize_field("data", data)?;
+        }
+        if links.len() > 0 {
+            state.serialize_field("links", &links)?;
+        }
+        if let Some(meta) = &self.meta {
+            state.serialize_field("meta", meta)?;
         }
+        state.end()
     }
 }
 
-pub struct RelationObject {
-    data: Vec<ResIdenObjNonGeneric>,
-    links: Vec<Link>,
+impl Serialize for RelationObject<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        self.serialize_in(None, serializer)
+    }
+}
+
+// Links of a relationship. The `self` and `related` links generated from the URL of the resource
+// follow the given ones, and are formatted while serializing. `self_link` is a `self` link used
+// as it is.
+struct RelationLinks<'a> {
+    relation: &'a RelationObject<'a>,
+    self_url: Option<&'a str>,
+    self_link: Option<&'a str>,
+    related_url: Option<&'a str>,
+}
+
+impl RelationLinks<'_> {
+    fn len(&self) -> usize {
+        self.relation.links.len()
+            + self.self_url.iter().count()
+            + self.self_link.iter().count()
+            + self.related_url.iter().count()
+    }
+}
+
+impl Serialize for RelationLinks<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
+    where
+        S: Serializer,
+    {
+        let name = self.relation.name;
+        let mut state = serializer.serialize_map(Some(self.len()))?;
+        for link in &self.relation.links {
+            match link {
+                Link::Url(key, url) => state.serialize_entry(key, url)?,
+                Link::Object(key, link_object) => state.serialize_entry(key, link_object)?,
+            }
+        }
+        if let Some(url) = self.self_url {
+            state.serialize_entry("self", &format_args!("{}/relationships/{}", url, name))?;
+        }
+        if let Some(self_link) = self.self_link {
+            state.serialize_entry("self", self_link)?;
+        }
+        if let Some(url) = self.related_url {
+            state.serialize_entry("related", &format_args!("{}/{}", url, name))?;
+        }
+        state.end()
+    }
 }
 
 pub trait RelationObjectify<'a, To>: HaveRelationship<'a, To> {
-    fn get_relation_object(&'a self) -> RelationObject;
+    /// Returns the relationship object of the relationship named `name`
+    fn get_relation_object(&'a self, name: &'static str) -> RelationObject<'a>;
 }
 
+/// Implemented on resources that have a relationship to `To`.
+///
+/// `To` can be a single resource, `Option` of a resource for a nullable to-one relationship, or
+/// a `JsonApiCollection` of resources for a to-many relationship.
 pub trait HaveRelationship<'a, To> {
     fn get_relation(&'a self) -> To;
+
+    /// Meta information of the relationship, serialized as the `meta` of its relationship object
+    fn get_relation_meta(&'a self) -> Option<Box<dyn RefSerialize>> {
+        None
+    }
+
+    /// Links of the relationship. A `self` or `related` link given here replaces the one generated
+    /// from the URL of the resource.
+    fn get_relation_links(&'a self) -> Vec<Link<'a>> {
+        vec![]
+    }
 }
 
+/// Implemented on resources to provide the relationship objects of their `relationships` member.
+/// Resources not implementing it are serialized without relationships.
+///
+/// ```rust
+/// # use rocket_jsonapi::relationship::{
+/// #     HaveRelationship, RelationObject, RelationObjectify, Relationships,
+/// # };
+/// # use rocket_jsonapi::{Linkify, ResourceIdentifiable, ResourceType};
+/// #[derive(ResourceType, ResourceIdentifiable, Linkify)]
+/// struct Person {
+///     id: i32,
+/// }
+///
+/// struct Article {
+///     id: i32,
+///     author_id: Option<i32>,
+/// }
+///
+/// impl HaveRelationship<'_, Option<Person>> for Article {
+///     fn get_relation(&self) -> Option<Person> {
+///         self.author_id.map(|id| Person { id })
+///     }
+/// }
+///
+/// impl Relationships for Article {
+///     fn get_all_relation_objects(&self) -> Vec<RelationObject> {
+///         vec![<Self as RelationObjectify<Option<Person>>>::get_relation_object(self, "author")]
+///     }
+/// }
+/// ```
 pub trait Relationships {
-    fn get_all_relation_objects(&self) -> Vec<RelationObject>;
+    fn get_all_relation_objects(&self) -> Vec<RelationObject<'_>>;
 }
 
-trait RelationObjectifyMeta<'a, Meta, To>: RelationObjectify<'a, To> {
-    fn get_meta() -> Meta;
+impl<T: Relationships> Relationships for &T {
+    fn get_all_relation_objects(&self) -> Vec<RelationObject<'_>> {
+        (**self).get_all_relation_objects()
+    }
+}
+
+impl_for_pointers! {
+    impl<T: Relationships> Relationships {
+        fn get_all_relation_objects(&self) -> Vec<RelationObject<'_>> {
+            (**self).get_all_relation_objects()
+        }
+    }
 }
 
 impl<'a, From, To> RelationObjectify<'a, To> for From
 where
-    To: ResourceIdentifiable + Linkify,
+    To: ResourceIdentifiable,
     From: HaveRelationship<'a, To>,
 {
-    default fn get_relation_object(&'a self) -> RelationObject {
+    default fn get_relation_object(&'a self, name: &'static str) -> RelationObject<'a> {
         let rel = self.get_relation();
         RelationObject {
-            data: vec![ResIdenObjNonGeneric {
-                id: rel.get_id().to_string(),
-                object_type: To::get_type(),
-            }],
-            links: rel.get_links(),
+            name,
+            data: Some(RelationData::ToOne(Some((&rel).into()))),
+            links: self.get_relation_links(),
+            meta: self.get_relation_meta(),
+            resource_links: true,
         }
     }
 }
 
-impl<'a, From, To> RelationObjectify<'a, JsonApiCollection<To>> for From
+impl<'a, From, To> RelationObjectify<'a, Option<To>> for From
 where
-    To: ResourceIdentifiable + Linkify,
-    From: HaveRelationship<'a, JsonApiCollection<To>>,
+    To: ResourceIdentifiable,
+    From: HaveRelationship<'a, Option<To>>,
 {
-    fn get_relation_object(&'a self) -> RelationObject {
+    fn get_relation_object(&'a self, name: &'static str) -> RelationObject<'a> {
         let rel = self.get_relation();
         RelationObject {
-            data: rel.0.iter().map(|to| to.into()).collect(),
-            links: rel.1,
+            name,
+            data: Some(RelationData::ToOne(rel.as_ref().map(|to| to.into()))),
+            links: self.get_relation_links(),
+            meta: self.get_relation_meta(),
+            resource_links: true,
+        }
+    }
+}
+
+impl<'a, From, To, Links> RelationObjectify<'a, JsonApiCollection<To, Links>> for From
+where
+    To: ResourceIdentifiable,
+    Links: CollectionLinks,
+    From: HaveRelationship<'a, JsonApiCollection<To, Links>>,
+{
+    fn get_relation_object(&'a self, name: &'static str) -> RelationObject<'a> {
+        let JsonApiCollection(data, links) = self.get_relation();
+        let (links, meta) = links.into_relation_parts();
+        RelationObject {
+            name,
+            data: Some(RelationData::ToMany(
+                data.iter().map(|to| to.into()).collect(),
+            )),
+            links: relation_links(self.get_relation_links(), links),
+            // Meta of the relationship takes precedence over the meta of the page
+            meta: self.get_relation_meta().or(meta),
+            resource_links: true,
+        }
+    }
+}
+
+// Links given by `HaveRelationship` replace pagination links with the same key
+fn relation_links<'a>(mut links: Vec<Link<'a>>, pagination: Vec<Link<'static>>) -> Vec<Link<'a>> {
+    let given = links.len();
+    for link in pagination {
+        if !links[..given].iter().any(|given| given.key() == link.key()) {
+            links.push(link);
         }
     }
+    links
 }
@@ -1,10 +1,15 @@
 //! # Validating JSON:API requests
-use crate::core::input_data::{JsonApiCreateResource, JsonApiUpdateResource};
+pub use crate::core::input_data::InputResource;
+use crate::core::input_data::{
+    unaccepted_type, unaccepted_types, JsonApiCreateResource, JsonApiUpdateResource,
+};
+use crate::core::negotiation::{negotiate, Negotiation};
+use crate::error::{JsonApiError, JsonApiErrorSource, JsonApiResponseError};
 use crate::lib::*;
-use crate::resource::ResourceType;
+use crate::resource::{ParseIdError, ResourceId, ResourceType};
 use rocket::data::{self, FromDataSimple};
-use rocket::http::{ContentType, MediaType, Status};
-use rocket::request::{self, FromRequest};
+use rocket::http::{RawStr, Status};
+use rocket::request::{self, FromParam, FromRequest};
 use rocket::Data;
 use rocket::Request;
 use serde::export::PhantomData;
@@ -13,52 +18,193 @@ use serde_json::{Map, Value};
 
 pub struct JsonApiRequest;
 // TODO add Option<ClientId>? How do we help users return a 403 if unsupported? Make enum?
+/// Data guard of a POST request creating a resource, deserialized as the [InputResource] `Data`.
+///
+/// `Data` is a single input type, or an enum deriving `InputResource` for endpoints accepting
+/// resource objects of several types. Documents with a `type` that is not accepted, or with
+/// attributes that can not be deserialized, are responded with 409 Conflict. The error describes
+/// the conflict, like the accepted types.
 pub struct JsonApiCreateRequest<Data>(pub Data);
-pub struct JsonApiUpdateRequest<Data> {
-    pub id: String,
+/// Data guard of a PATCH request updating a resource of type `Data`.
+///
+/// The `id` of the request document is parsed as `Id`, a `String` unless another [ResourceId] is
+/// given, like `JsonApiUpdateRequest<Article, i32>`. Documents with an id that can not be parsed
+/// are responded with 409 Conflict, like documents of another type. The error points to the
+/// conflicting member of the document.
+pub struct JsonApiUpdateRequest<Data, Id = String> {
+    pub id: Id,
     pub attributes: Map<String, Value>,
     phantom: PhantomData<Data>,
 }
 
+/// Route parameter parsed as the [ResourceId] `Id`, for ids that do not implement `FromParam`,
+/// like composite keys:
+///
+/// ```rust
+/// # #![feature(proc_macro_hygiene, decl_macro)]
+/// # #[macro_use] extern crate rocket;
+/// # use rocket_jsonapi::ResourceId;
+/// # use rocket_jsonapi::request::ResourceIdParam;
+/// #[derive(ResourceId)]
+/// struct PostKey {
+///     author_id: i32,
+///     slug: String,
+/// }
+///
+/// #[get("/posts/<key>")]
+/// fn post(key: ResourceIdParam<PostKey>) -> String {
+///     format!("{} by {}", key.0.slug, key.0.author_id)
+/// }
+/// ```
+pub struct ResourceIdParam<Id>(pub Id);
+
+impl<'a, Id: ResourceId> FromParam<'a> for ResourceIdParam<Id> {
+    type Error = ParseIdError;
+
+    fn from_param(param: &'a RawStr) -> Result<Self, Self::Error> {
+        let id = param
+            .percent_decode()
+            .map_err(|_| ParseIdError::new(param.as_str()))?;
+        Id::parse_id(&id).map(ResourceIdParam)
+    }
+}
+
+/// The JSON:API extensions and profiles a server supports.
+///
+/// JSON:API v1.1 allows the `ext` and `profile` media type parameters in the `Content-Type` and
+/// `Accept` headers. Register what your server supports by managing an instance of this type,
+/// otherwise any `ext` parameter is rejected:
+///
+/// ```rust
+/// # use rocket_jsonapi::request::MediaTypeSupport;
+/// rocket::ignite().manage(
+///     MediaTypeSupport::new()
+///         .extension("https://jsonapi.org/ext/atomic")
+///         .profile("https://example.com/profiles/last-modified"),
+/// );
+/// ```
+///
+/// Requests using unsupported extensions are responded with 415 Unsupported Media Type or
+/// 406 Not Acceptable, unsupported profiles are ignored. The applied extensions and profiles are
+/// echoed in the `Content-Type` of the response.
+#[derive(Clone, Debug, Default)]
+pub struct MediaTypeSupport {
+    extensions: Vec<String>,
+    profiles: Vec<String>,
+}
+
+impl MediaTypeSupport {
+    /// Constructs an instance supporting no extensions or profiles
+    pub fn new() -> Self {
+        MediaTypeSupport::default()
+    }
+
+    /// Adds support for the extension identified by `uri`
+    pub fn extension<U: Into<String>>(mut self, uri: U) -> Self {
+        self.extensions.push(uri.into());
+        self
+    }
+
+    /// Adds support for the profile identified by `uri`
+    pub fn profile<U: Into<String>>(mut self, uri: U) -> Self {
+        self.profiles.push(uri.into());
+        self
+    }
+
+    /// Returns true if the extension identified by `uri` is supported
+    pub fn supports_extension(&self, uri: &str) -> bool {
+        self.extensions.iter().any(|extension| extension == uri)
+    }
+
+    /// Returns true if the profile identified by `uri` is supported
+    pub fn supports_profile(&self, uri: &str) -> bool {
+        self.profiles.iter().any(|profile| profile == uri)
+    }
+}
+
+/// Request guard exposing the JSON:API extensions and profiles negotiated for a request.
+///
+/// Fails with 406 Not Acceptable or 415 Unsupported Media Type like the other guards of this
+/// module, see `MediaTypeSupport`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NegotiatedMediaType {
+    extensions: Vec<String>,
+    profiles: Vec<String>,
+    request_extensions: Vec<String>,
+}
+
+impl NegotiatedMediaType {
+    /// Extensions applied to the response, they are echoed in its `Content-Type`
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    /// Supported profiles requested through the `Accept` or `Content-Type` header
+    pub fn profiles(&self) -> &[String] {
+        &self.profiles
+    }
+
+    /// Extensions the request document was sent with, through its `Content-Type`
+    pub fn request_extensions(&self) -> &[String] {
+        &self.request_extensions
+    }
+
+    /// Returns true if the profile identified by `uri` was negotiated
+    pub fn has_profile(&self, uri: &str) -> bool {
+        self.profiles.iter().any(|profile| profile == uri)
+    }
+
+    /// Returns true if the request document was sent with the extension identified by `uri`
+    pub fn has_request_extension(&self, uri: &str) -> bool {
+        self.request_extensions
+            .iter()
+            .any(|extension| extension == uri)
+    }
+}
+
+fn negotiated_media_type(negotiation: &Negotiation) -> Result<NegotiatedMediaType, Status> {
+    let accept = negotiation.accept.as_ref().map_err(|status| *status)?;
+    let mut media_type = NegotiatedMediaType {
+        extensions: accept.extensions.clone(),
+        profiles: accept.profiles.clone(),
+        request_extensions: vec![],
+    };
+    match &negotiation.content_type {
+        Some(Ok(content_type)) => {
+            media_type.request_extensions = content_type.extensions.clone();
+            for profile in &content_type.profiles {
+                if !media_type.has_profile(profile) {
+                    media_type.profiles.push(profile.clone());
+                }
+            }
+            Ok(media_type)
+        }
+        Some(Err(status)) => Err(*status),
+        None => Ok(media_type),
+    }
+}
+
 fn acceptable_json_api_content_type(request: &Request) -> Result<(), Status> {
-    // JSON API v. 1.0
-    // Servers MUST respond with a 415 Unsupported Media Type status code if a request specifies
-    // the header Content-Type: application/vnd.api+json with any media type parameters.
-    let content_type_media_type_test = request
-        .content_type()
-        .filter(|content_type| {
-            (**content_type) == ContentType::JsonApi && content_type.0.params().count() == 0
-        })
-        .is_some();
-    if !content_type_media_type_test {
-        // Specification states only what the status code must be, user must decide themselves
-        // via rocket error catcher what to respond with
-        return Err(Status::UnsupportedMediaType);
-    }
-    Ok(())
+    // Specification states only what the status code must be, user must decide themselves
+    // via rocket error catcher what to respond with
+    match &negotiate(request).content_type {
+        Some(Ok(_)) => Ok(()),
+        Some(Err(status)) => Err(*status),
+        None => Err(Status::UnsupportedMediaType),
+    }
 }
 
 fn acceptable_json_api_accept(request: &Request) -> Result<(), Status> {
-    // JSON API v. 1.0
-    // Servers MUST respond with a 406 Not Acceptable status code if a request’s Accept header
-    // contains the JSON:API media type and all instances of that media type are modified with
-    // media type parameters.
-    let accept_test = request
-        .accept()
-        .iter()
-        .flat_map(|accept| accept.iter())
-        .any(|query_media_type| {
-            query_media_type.0 == MediaType::JsonApi && query_media_type.0.params().count() == 0
-        });
-    if !accept_test {
-        // Specification states only what the status code must be, user must decide themselves
-        // via rocket error catcher what to respond with
-        return Err(Status::NotAcceptable);
-    }
-    Ok(())
+    // Specification states only what the status code must be, user must decide themselves
+    // via rocket error catcher what to respond with
+    negotiate(request)
+        .accept
+        .as_ref()
+        .map(|_| ())
+        .map_err(|status| *status)
 }
 
-fn acceptable_json_api_data_request(request: &Request) -> Result<(), Status> {
+pub(crate) fn acceptable_json_api_data_request(request: &Request) -> Result<(), Status> {
     acceptable_json_api_accept(request)?;
     acceptable_json_api_content_type(request)
 }
@@ -75,14 +221,24 @@ impl<'a, 'r> FromRequest<'a, 'r> for JsonApiRequest {
     }
 }
 
+impl<'a, 'r> FromRequest<'a, 'r> for NegotiatedMediaType {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        match negotiated_media_type(negotiate(request)) {
+            Ok(media_type) => request::Outcome::Success(media_type),
+            Err(status) => request::Outcome::Failure((status, ())),
+        }
+    }
+}
+
 impl<InputData> FromDataSimple for JsonApiCreateRequest<InputData>
 where
-    for<'de> InputData: ResourceType + Deserialize<'de>,
+    InputData: InputResource,
 {
-    // TODO right error?
     // A server SHOULD include error details and provide enough information to recognize the source
     // of the conflict.
-    type Error = ();
+    type Error = JsonApiResponseError;
 
     fn from_data(request: &Request, data: Data) -> data::Outcome<Self, Self::Error> {
         match acceptable_json_api_data_request(request) {
@@ -91,29 +247,56 @@ where
                 let b: Result<JsonApiCreateResource<InputData>, serde_json::error::Error> =
                     serde_json::from_reader(data.open());
                 match b {
-                    Ok(result) => data::Outcome::Success(JsonApiCreateRequest(result.data.0)),
-                    Err(err) => match err.classify() {
-                        // A server MUST return 409 Conflict when processing a POST request in which
-                        // the resource object’s type is not among the type(s) that constitute the
-                        // collection represented by the endpoint.
-                        Category::Data => data::Outcome::Failure((Status::Conflict, ())),
-                        _ => data::Outcome::Failure((Status::BadRequest, ())),
+                    Ok(result) => match result.data.0 {
+                        Ok(input) => data::Outcome::Success(JsonApiCreateRequest(input)),
+                        // A server MUST return 409 Conflict when processing a POST request in
+                        // which the resource object’s type is not among the type(s) that
+                        // constitute the collection represented by the endpoint.
+                        Err(resource_type) => {
+                            let status = Status::Conflict;
+                            let error = JsonApiError {
+                                status: Some(status.code.to_string()),
+                                detail: Some(
+                                    unaccepted_type::<InputData, serde_json::Error>(&resource_type)
+                                        .to_string(),
+                                ),
+                                source: Some(JsonApiErrorSource::pointer("/data/type")),
+                                ..Default::default()
+                            };
+                            data::Outcome::Failure((
+                                status,
+                                JsonApiResponseError::new(status, vec![error]),
+                            ))
+                        }
                     },
+                    Err(err) => {
+                        let status = match err.classify() {
+                            // Attributes that can not be deserialized conflict with the input type
+                            Category::Data => Status::Conflict,
+                            _ => Status::BadRequest,
+                        };
+                        data::Outcome::Failure((
+                            status,
+                            JsonApiResponseError::from_error(status, err),
+                        ))
+                    }
                 }
             }
-            Err(status) => data::Outcome::Failure((status, ())),
+            Err(status) => {
+                data::Outcome::Failure((status, JsonApiResponseError::new(status, vec![])))
+            }
         }
     }
 }
 
-impl<InputData> FromDataSimple for JsonApiUpdateRequest<InputData>
+impl<InputData, Id> FromDataSimple for JsonApiUpdateRequest<InputData, Id>
 where
     for<'de> InputData: ResourceType + Deserialize<'de>,
+    Id: ResourceId,
 {
-    // TODO right error?
     // A server SHOULD include error details and provide enough information to recognize the source
     // of the conflict.
-    type Error = ();
+    type Error = JsonApiResponseError;
 
     fn from_data(request: &Request, data: Data) -> data::Outcome<Self, Self::Error> {
         match acceptable_json_api_data_request(request) {
@@ -124,24 +307,54 @@ where
                 match b {
                     Ok(result) => {
                         if result.data.resource_type != InputData::get_type() {
-                            return data::Outcome::Failure((Status::Conflict, ()));
+                            let error: serde_json::Error = unaccepted_types(
+                                &result.data.resource_type,
+                                &[InputData::get_type()],
+                            );
+                            return data::Outcome::Failure(conflict(error, "/data/type"));
                         }
-                        data::Outcome::Success(JsonApiUpdateRequest::<InputData> {
-                            id: result.data.id,
+                        let id = match Id::parse_id(&result.data.id) {
+                            Ok(id) => id,
+                            Err(error) => {
+                                return data::Outcome::Failure(conflict(error, "/data/id"))
+                            }
+                        };
+                        data::Outcome::Success(JsonApiUpdateRequest {
+                            id,
                             attributes: result.data.attributes,
                             phantom: PhantomData,
                         })
                     }
-                    Err(err) => match err.classify() {
-                        // Specification: A server MUST return 409 Conflict when processing a PATCH
-                        // request in which the resource object’s type and id do not match the
-                        // server’s endpoint.
-                        Category::Data => data::Outcome::Failure((Status::Conflict, ())),
-                        _ => data::Outcome::Failure((Status::BadRequest, ())),
-                    },
+                    Err(err) => {
+                        let status = match err.classify() {
+                            // Specification: A server MUST return 409 Conflict when processing a
+                            // PATCH request in which the resource object’s type and id do not
+                            // match the server’s endpoint.
+                            Category::Data => Status::Conflict,
+                            _ => Status::BadRequest,
+                        };
+                        data::Outcome::Failure((
+                            status,
+                            JsonApiResponseError::from_error(status, err),
+                        ))
+                    }
                 }
             }
-            Err(status) => data::Outcome::Failure((status, ())),
+            Err(status) => {
+                data::Outcome::Failure((status, JsonApiResponseError::new(status, vec![])))
+            }
         }
     }
 }
+
+// 409 Conflict with the member of the request document at `pointer`
+fn conflict<E: std::fmt::Display>(error: E, pointer: &str) -> (Status, JsonApiResponseError) {
+    let status = Status::Conflict;
+    let error = JsonApiError {
+        status: Some(status.code.to_string()),
+        detail: Some(error.to_string()),
+        source: Some(JsonApiErrorSource::pointer(pointer)),
+        ..Default::default()
+    };
+    (status, JsonApiResponseError::new(status, vec![error]))
+}
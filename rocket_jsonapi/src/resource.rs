@@ -1,10 +1,23 @@
 use crate::lib::*;
+use erased_serde::Serialize as RefSerialize;
+use serde::ser::SerializeMap;
 
 pub trait ResourceType {
     /// Returns the resource type
     fn get_type() -> &'static str;
 }
 
+/// The type of a resource instance, for resources whose instances are not all of the same type,
+/// like an enum of resources of several types.
+///
+/// Resource objects and resource identifier objects of these resources are serialized with the
+/// type of the instance instead of [ResourceType::get_type]. Resources that do not implement it
+/// keep using the static type.
+pub trait InstanceType: ResourceType {
+    /// Returns the type of this instance
+    fn instance_type(&self) -> &str;
+}
+
 pub trait ResourceIdentifiable: ResourceType {
     /// Trait implemented on data objects so they can be parsed as resource objects.
     ///
@@ -39,7 +52,13 @@ pub trait ResourceIdentifiable: ResourceType {
     ///
     /// `#[resource_ident_id = "id_field"]` changes the field that functions as the `id`.
     ///
-    /// `#[resource_ident_type = "CustomType"]` changes the `type`.
+    /// `#[resource_ident_type = "CustomType"]` changes the `type`. Any string is allowed, like
+    /// `"blog-posts"`.
+    ///
+    /// `#[resource_ident_type(rename_all = "kebab-case", plural)]` derives the `type` from the
+    /// struct name instead, so `BlogPost` becomes `"blog-posts"`. The supported rules are
+    /// `"kebab-case"`, `"snake_case"` and `"camelCase"`, and `plural` can be used with or without
+    /// a rule.
     ///
     /// Example:
     /// ```rust
@@ -62,3 +81,238 @@ pub trait ResourceIdentifiable: ResourceType {
     /// Returns the resource id
     fn get_id(&self) -> &Self::IdType;
 }
+
+/// The id of a resource, formatted as the string `id` of its resource object and parsed back from
+/// routes and request documents.
+///
+/// Implemented for the integer types and `String`, and for `uuid::Uuid` with the `uuid` feature.
+/// Ids made of several values, like composite keys, can derive it:
+/// ```rust
+/// # use rocket_jsonapi::ResourceId;
+/// #[derive(ResourceId)]
+/// struct PostKey {
+///     author_id: i32,
+///     slug: String,
+/// }
+/// let key = PostKey::parse_id("3-hello-world").unwrap();
+/// assert_eq!(key.author_id, 3);
+/// assert_eq!(key.slug, "hello-world");
+/// assert_eq!(key.format_id(), "3-hello-world");
+/// ```
+/// The derived id joins its fields with `-`, and the last field takes the rest of the string when
+/// parsing. In the other fields `%` and the separator are percent-encoded, so ids like negative
+/// integers or UUIDs round-trip. `#[resource_id(separator = ":")]` changes the separator, which
+/// can not contain `%` or hex digits. The derive also implements `Display`, so the id can be used
+/// as the `IdType` of a [ResourceIdentifiable].
+pub trait ResourceId: Sized {
+    /// Formats the id as the `id` of a resource object
+    fn format_id(&self) -> String;
+
+    /// Parses an id formatted by `format_id`
+    fn parse_id(id: &str) -> Result<Self, ParseIdError>;
+}
+
+/// Error of a string that is not a valid id of the resource
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseIdError {
+    id: String,
+}
+
+impl ParseIdError {
+    pub fn new<I: Into<String>>(id: I) -> Self {
+        ParseIdError { id: id.into() }
+    }
+
+    /// The string that could not be parsed
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl std::fmt::Display for ParseIdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid resource id `{}`", self.id)
+    }
+}
+
+impl std::error::Error for ParseIdError {}
+
+macro_rules! impl_resource_id_from_str {
+    ($($id_type:ty),*) => {
+        $(
+            impl ResourceId for $id_type {
+                fn format_id(&self) -> String {
+                    self.to_string()
+                }
+
+                fn parse_id(id: &str) -> Result<Self, ParseIdError> {
+                    id.parse().map_err(|_| ParseIdError::new(id))
+                }
+            }
+        )*
+    };
+}
+
+impl_resource_id_from_str!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl ResourceId for String {
+    fn format_id(&self) -> String {
+        self.clone()
+    }
+
+    fn parse_id(id: &str) -> Result<Self, ParseIdError> {
+        Ok(id.to_string())
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl_resource_id_from_str!(uuid::Uuid);
+
+/// Meta information of a resource, serialized as the `meta` member of its resource object.
+///
+/// Resources without meta information simply do not implement it. The `meta` of
+/// `#[derive(JsonApiResource)]` implements it with a [Meta] map:
+/// ```rust
+/// # use rocket_jsonapi::JsonApiResource;
+/// # use serde::Serialize;
+/// #[derive(Serialize, JsonApiResource)]
+/// #[jsonapi(meta(word_count = self.text.split_whitespace().count()))]
+/// struct Article {
+///     id: i32,
+///     text: String,
+/// }
+/// ```
+pub trait ResourceMeta {
+    type Meta: Serialize;
+
+    /// Returns the meta information of the resource
+    fn get_meta(&self) -> Self::Meta;
+}
+
+/// A map of meta information with values of any serializable type.
+///
+/// ```rust
+/// # use rocket_jsonapi::resource::Meta;
+/// let meta = Meta::new().with("count", 2).with("status", "draft");
+/// ```
+#[derive(Default)]
+pub struct Meta(Vec<(&'static str, Box<dyn RefSerialize>)>);
+
+impl Meta {
+    pub fn new() -> Self {
+        Meta(vec![])
+    }
+
+    /// Adds the member `key` to the map
+    pub fn with<M: Serialize + 'static>(mut self, key: &'static str, value: M) -> Self {
+        self.0.push((key, Box::new(value)));
+        self
+    }
+}
+
+impl Serialize for Meta {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in &self.0 {
+            state.serialize_entry(key, value)?;
+        }
+        state.end()
+    }
+}
+
+// References forward to the resource, so borrowed resources and relations can be served
+impl<T: ResourceType> ResourceType for &T {
+    fn get_type() -> &'static str {
+        T::get_type()
+    }
+}
+
+impl<T: InstanceType> InstanceType for &T {
+    fn instance_type(&self) -> &str {
+        (**self).instance_type()
+    }
+}
+
+impl<T: ResourceIdentifiable> ResourceIdentifiable for &T {
+    type IdType = T::IdType;
+
+    fn get_id(&self) -> &Self::IdType {
+        (**self).get_id()
+    }
+}
+
+impl<T: ResourceMeta> ResourceMeta for &T {
+    type Meta = T::Meta;
+
+    fn get_meta(&self) -> Self::Meta {
+        (**self).get_meta()
+    }
+}
+
+impl_for_pointers! {
+    impl<T: ResourceType> ResourceType {
+        fn get_type() -> &'static str {
+            T::get_type()
+        }
+    }
+}
+
+impl_for_pointers! {
+    impl<T: InstanceType> InstanceType {
+        fn instance_type(&self) -> &str {
+            (**self).instance_type()
+        }
+    }
+}
+
+impl_for_pointers! {
+    impl<T: ResourceIdentifiable> ResourceIdentifiable {
+        type IdType = T::IdType;
+
+        fn get_id(&self) -> &Self::IdType {
+            (**self).get_id()
+        }
+    }
+}
+
+impl_for_pointers! {
+    impl<T: ResourceMeta> ResourceMeta {
+        type Meta = T::Meta;
+
+        fn get_meta(&self) -> Self::Meta {
+            (**self).get_meta()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resource::{ParseIdError, ResourceId};
+
+    #[test]
+    fn resource_id_integer_round_trip() {
+        assert_eq!(42u64.format_id(), "42");
+        assert_eq!(i32::parse_id("-7"), Ok(-7));
+        assert_eq!(i32::parse_id(&(-7).format_id()), Ok(-7));
+        assert_eq!(u8::parse_id("256"), Err(ParseIdError::new("256")));
+        assert_eq!(i64::parse_id("abc"), Err(ParseIdError::new("abc")));
+    }
+
+    #[test]
+    fn resource_id_string_round_trip() {
+        assert_eq!(String::parse_id("hello-world").unwrap(), "hello-world");
+        assert_eq!("hello-world".to_string().format_id(), "hello-world");
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn resource_id_uuid_round_trip() {
+        let id = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+        let uuid = uuid::Uuid::parse_id(id).unwrap();
+        assert_eq!(uuid.format_id(), id);
+        assert!(uuid::Uuid::parse_id("67e55044").is_err());
+    }
+}
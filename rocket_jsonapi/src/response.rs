@@ -3,25 +3,84 @@
 //! This module exports types for responding with JSON:API compliant responses.
 //!
 //! This excludes the types for metadata like `links` and `relationship`.
-use crate::core::data_object::JsonApiPrimaryDataObject;
-use crate::core::general_response::JsonApiResponse;
+use crate::core::general_response::{
+    respond_created, respond_in, JsonApiDocument, JsonApiResponse,
+};
+use crate::core::link_context::link_context;
+use crate::core::negotiation::response_content_type;
+use crate::core::relationships_serialize::RelationDocument;
 use crate::error::{JsonApiError, JsonApiResponseError};
 use crate::lib::*;
-use rocket::http::{ContentType, Status};
+use crate::relationship::{HaveRelationship, RelationObjectify};
+use erased_serde::Serialize as RefSerialize;
+use rocket::http::Status;
 use rocket::response::Responder;
 use rocket::{Request, Response};
+use std::io::Cursor;
+use std::marker::PhantomData;
 
-pub struct JsonApiCollection<Data>(pub Vec<Data>, pub Vec<Link>);
+/// A collection of resources, with the links of the collection.
+///
+/// The links are a `Vec<Link>`, or a [Pagination] for a page of a larger collection, see
+/// `JsonApiCollection::paginated`.
+pub struct JsonApiCollection<Data, Links = Vec<Link<'static>>>(pub Vec<Data>, pub Links);
 
 impl<Data> JsonApiCollection<Data> {
     pub fn data(vec: Vec<Data>) -> Self {
         JsonApiCollection(vec, vec![])
     }
-    pub fn data_w_links(links: Vec<Link>, vec: Vec<Data>) -> Self {
+    pub fn data_w_links(links: Vec<Link<'static>>, vec: Vec<Data>) -> Self {
         JsonApiCollection(vec, links)
     }
 }
 
+impl<Data> JsonApiCollection<Data, Pagination> {
+    /// A page of a collection. Used as a to-many relationship, the pagination links and meta are
+    /// part of the relationship object.
+    pub fn paginated(vec: Vec<Data>, pagination: Pagination) -> Self {
+        JsonApiCollection(vec, pagination)
+    }
+}
+
+/// Links of a [JsonApiCollection], implemented for `Vec<Link>` and [Pagination]
+pub trait CollectionLinks {
+    /// Top-level links of the collection
+    fn links(&self) -> &[Link<'_>];
+
+    /// Top-level meta of the collection
+    fn meta(&self) -> Option<&dyn RefSerialize> {
+        None
+    }
+
+    /// Pagination links and meta of the collection as a to-many relationship
+    fn into_relation_parts(self) -> (Vec<Link<'static>>, Option<Box<dyn RefSerialize>>);
+}
+
+impl CollectionLinks for Vec<Link<'_>> {
+    fn links(&self) -> &[Link<'_>] {
+        self
+    }
+
+    // Links of a whole collection are top-level links, not links of the relationship
+    fn into_relation_parts(self) -> (Vec<Link<'static>>, Option<Box<dyn RefSerialize>>) {
+        (vec![], None)
+    }
+}
+
+impl CollectionLinks for Pagination {
+    fn links(&self) -> &[Link<'_>] {
+        &self.links
+    }
+
+    fn meta(&self) -> Option<&dyn RefSerialize> {
+        self.meta.as_deref()
+    }
+
+    fn into_relation_parts(self) -> (Vec<Link<'static>>, Option<Box<dyn RefSerialize>>) {
+        self.into_parts()
+    }
+}
+
 /// # JSON:API Responder
 ///
 /// Responder for Rocket.rs route that responds with a JSON:API response. This object
@@ -80,10 +139,16 @@ impl<Data> JsonApiCollection<Data> {
 ///             "id": 5,
 ///             "message": "Hello!"
 ///         }
+///     },
+///     "links": {
+///         "self": "http://localhost:8000/simple"
 ///     }
 /// }
 /// ```
 ///
+/// The top-level `self` link is the URI of the request, unless `Linkify` provides one. See
+/// `LinkConfig` to opt out.
+///
 /// ## Errors
 ///
 /// When returning errors, you must construct an instance of `JsonApiResponseError`, and construct
@@ -142,12 +207,24 @@ where
     }
 }
 
-impl<'r, Data> Responder<'r> for JsonApiDataResponse<JsonApiCollection<Data>>
+impl<'r, Data, Links> Responder<'r> for JsonApiDataResponse<JsonApiCollection<Data, Links>>
 where
     Data: Serialize + ResourceIdentifiable + Linkify,
+    Links: CollectionLinks,
 {
     fn respond_to(self, request: &Request<'_>) -> Result<Response<'r>, Status> {
-        let general_response: JsonApiResponse<JsonApiCollection<Data>> = self.into();
+        let general_response: JsonApiResponse<JsonApiCollection<Data, Links>> = self.into();
+        general_response.respond_to(request)
+    }
+}
+
+/// Responds with `{"data": null}` for `None`, e.g. for an empty to-one related resource endpoint
+impl<'r, Data> Responder<'r> for JsonApiDataResponse<Option<Data>>
+where
+    Data: Serialize + ResourceIdentifiable + Linkify,
+{
+    fn respond_to(self, request: &Request<'_>) -> Result<Response<'r>, Status> {
+        let general_response: JsonApiResponse<Option<Data>> = self.into();
         general_response.respond_to(request)
     }
 }
@@ -161,8 +238,195 @@ impl<Data> From<JsonApiDataResponse<Data>> for JsonApiResponse<Data> {
     }
 }
 
+/// # Related resource Responder
+///
+/// Responder for related resource endpoints like `/articles/1/author`. The handler returns the
+/// parent resource, and the related resource(s) it has through `HaveRelationship<Rel>` are the
+/// primary data of the response. `Rel` can be a single resource, an `Option` of a resource, which
+/// responds with `"data": null` when empty, or a `JsonApiCollection`, which responds with
+/// `"data": []` when empty.
+///
+/// If the URL of the parent is known, from its `self` link or the managed `ResourceRoutes`, the
+/// top-level `self` link is the URL of the relationship's related resource endpoint, and the
+/// `related` link is the URL of the parent.
+///
+/// ```rust
+/// # #![feature(decl_macro)]
+/// # #[macro_use]
+/// # use rocket::*;
+/// # use rocket_jsonapi::relationship::HaveRelationship;
+/// # use rocket_jsonapi::response::JsonApiRelatedResponse;
+/// # use rocket_jsonapi::{Linkify, ResourceType, ResourceIdentifiable};
+/// # use serde::Serialize;
+/// #[derive(Serialize, ResourceType, ResourceIdentifiable, Linkify)]
+/// struct Person {
+///     id: i32,
+/// }
+///
+/// #[derive(Serialize, ResourceType, ResourceIdentifiable, Linkify)]
+/// struct Article {
+///     id: i32,
+///     #[serde(skip_serializing)]
+///     author_id: Option<i32>,
+/// }
+///
+/// impl HaveRelationship<'_, Option<Person>> for Article {
+///     fn get_relation(&self) -> Option<Person> {
+///         self.author_id.map(|id| Person { id })
+///     }
+/// }
+///
+/// #[get("/articles/<id>/author")]
+/// fn article_author(id: i32) -> JsonApiRelatedResponse<Article, Option<Person>> {
+///     JsonApiRelatedResponse::new("author", Ok(Article { id, author_id: Some(2) }))
+/// }
+/// ```
+pub struct JsonApiRelatedResponse<Parent, Rel> {
+    /// Name of the relationship, the last segment of the endpoint path
+    pub name: &'static str,
+    pub parent: Result<Parent, JsonApiResponseError>,
+    relation: PhantomData<Rel>,
+}
+
+impl<Parent, Rel> JsonApiRelatedResponse<Parent, Rel> {
+    pub fn new(name: &'static str, parent: Result<Parent, JsonApiResponseError>) -> Self {
+        JsonApiRelatedResponse {
+            name,
+            parent,
+            relation: PhantomData,
+        }
+    }
+}
+
+impl<'r, Parent, Rel> Responder<'r> for JsonApiRelatedResponse<Parent, Rel>
+where
+    Parent: ResourceIdentifiable + Linkify + for<'a> HaveRelationship<'a, Rel>,
+    for<'a> JsonApiDocument<'a, Rel>: Serialize,
+{
+    fn respond_to(self, request: &Request<'_>) -> Result<Response<'r>, Status> {
+        let context = link_context(request);
+        let parent = match self.parent {
+            Ok(parent) => parent,
+            Err(err) => {
+                return respond_in(JsonApiResponse::<Rel>(err.0, Err(err.1)), context, request)
+            }
+        };
+        let context = match context.resource_url(&parent) {
+            Some(parent_url) => context
+                .clone()
+                .with_self_link(format!("{}/{}", parent_url, self.name))
+                .with_related_link(parent_url),
+            None => context.clone(),
+        };
+        respond_in(
+            JsonApiResponse(Status::Ok, Ok(parent.get_relation())),
+            &context,
+            request,
+        )
+    }
+}
+
+/// # Relationship Responder
+///
+/// Responder for relationship endpoints like `/articles/1/relationships/comments`. The handler
+/// returns the parent resource, and the document is the relationship object it has through
+/// `RelationObjectify<Rel>`: the resource linkage as `data`, with the `links` and `meta` of the
+/// relationship. A page of a to-many relationship, see `JsonApiCollection::paginated`, adds its
+/// pagination links and meta.
+///
+/// The top-level `self` link is the request URI, so a page keeps its query, unless the
+/// relationship has a `self` link or `LinkConfig::without_self_link` is managed. If the URL of the
+/// parent is known, from its `self` link or the managed `ResourceRoutes`, the `related` link is
+/// the URL of the related resource endpoint.
+///
+/// ```rust
+/// # #![feature(decl_macro)]
+/// # #[macro_use]
+/// # use rocket::*;
+/// # use rocket_jsonapi::links::Pagination;
+/// # use rocket_jsonapi::relationship::HaveRelationship;
+/// # use rocket_jsonapi::response::{JsonApiCollection, JsonApiRelationshipResponse};
+/// # use rocket_jsonapi::{Linkify, ResourceType, ResourceIdentifiable};
+/// # use serde::Serialize;
+/// #[derive(Serialize, ResourceType, ResourceIdentifiable, Linkify)]
+/// struct Comment {
+///     id: i32,
+/// }
+///
+/// #[derive(ResourceType, ResourceIdentifiable, Linkify)]
+/// struct Article {
+///     id: i32,
+///     page: i32,
+/// }
+///
+/// impl HaveRelationship<'_, JsonApiCollection<Comment, Pagination>> for Article {
+///     fn get_relation(&self) -> JsonApiCollection<Comment, Pagination> {
+///         let url = |page| format!("/articles/{}/relationships/comments?page={}", self.id, page);
+///         JsonApiCollection::paginated(
+///             vec![Comment { id: self.page }],
+///             Pagination::new().first(url(1)).next(url(self.page + 1)),
+///         )
+///     }
+/// }
+///
+/// #[get("/articles/<id>/relationships/comments?<page>")]
+/// fn article_comments(
+///     id: i32,
+///     page: i32,
+/// ) -> JsonApiRelationshipResponse<Article, JsonApiCollection<Comment, Pagination>> {
+///     JsonApiRelationshipResponse::new("comments", Ok(Article { id, page }))
+/// }
+/// ```
+pub struct JsonApiRelationshipResponse<Parent, Rel> {
+    /// Name of the relationship, the last segment of the endpoint path
+    pub name: &'static str,
+    pub parent: Result<Parent, JsonApiResponseError>,
+    relation: PhantomData<Rel>,
+}
+
+impl<Parent, Rel> JsonApiRelationshipResponse<Parent, Rel> {
+    pub fn new(name: &'static str, parent: Result<Parent, JsonApiResponseError>) -> Self {
+        JsonApiRelationshipResponse {
+            name,
+            parent,
+            relation: PhantomData,
+        }
+    }
+}
+
+impl<'r, Parent, Rel> Responder<'r> for JsonApiRelationshipResponse<Parent, Rel>
+where
+    Parent: ResourceIdentifiable + Linkify + for<'a> RelationObjectify<'a, Rel>,
+{
+    fn respond_to(self, request: &Request<'_>) -> Result<Response<'r>, Status> {
+        let (status, body) = match self.parent {
+            Ok(parent) => {
+                let context = link_context(request);
+                let parent_url = context.resource_url(&parent);
+                let relation = parent.get_relation_object(self.name);
+                let body = serde_json::to_vec(&RelationDocument(
+                    &relation,
+                    parent_url.as_deref(),
+                    context.self_link(),
+                ));
+                (Status::Ok, body)
+            }
+            Err(error) => (error.0, serde_json::to_vec(&error)),
+        };
+        let body = body.map_err(|_e| Status::InternalServerError)?;
+        Ok(Response::build()
+            .header(response_content_type(request))
+            .status(status)
+            .sized_body(Cursor::new(body))
+            .finalize())
+    }
+}
+
 pub enum JsonApiCreateResponse<Data> {
     /// Data is accepted and created, [spec](https://jsonapi.org/format/#crud-creating-responses-201)
+    ///
+    /// The `Location` header is set to the `self` link of the resource from `Linkify`, or to its
+    /// path in the managed `ResourceRoutes`.
     Created(Data),
     /// Used when data is accepted, but maybe needs asynchronous processing and is not created yet,
     /// [spec](https://jsonapi.org/format/#crud-creating-responses-202)
@@ -186,13 +450,15 @@ where
     fn respond_to(self, request: &Request<'_>) -> Result<Response<'r>, Status> {
         match self {
             JsonApiCreateResponse::Created(data) => {
-                JsonApiResponse(Status::Created, Ok(data)).respond_to(request)
+                // A 201 response SHOULD include a Location header identifying the location of the
+                // newly created resource.
+                respond_created(data, request)
             }
             JsonApiCreateResponse::Accepted(data) => {
                 JsonApiResponse(Status::Accepted, Ok(data)).respond_to(request)
             }
             JsonApiCreateResponse::NoContent => Ok(Response::build()
-                .header(ContentType::JsonApi)
+                .header(response_content_type(request))
                 .status(Status::NoContent)
                 .finalize()),
             JsonApiCreateResponse::UnsupportedClientId(error) => {
@@ -262,7 +528,7 @@ where
                 JsonApiResponse(Status::Accepted, Ok(data)).respond_to(request)
             }
             JsonApiUpdateResponse::NoContent => Ok(Response::build()
-                .header(ContentType::JsonApi)
+                .header(response_content_type(request))
                 .status(Status::NoContent)
                 .finalize()),
             JsonApiUpdateResponse::Forbidden(error) => {
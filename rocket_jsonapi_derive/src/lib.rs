@@ -3,197 +3,925 @@ extern crate proc_macro;
 use crate::proc_macro::TokenStream;
 use proc_macro2::{Ident, Span};
 use quote::quote;
-use std::error::Error;
 use syn;
-use syn::export::Formatter;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
 use syn::Lit::Str;
 use syn::Meta::{List, NameValue};
-use syn::MetaNameValue;
 
-#[derive(Debug)]
-struct ErrorMessage(String);
+// Value of a link in `#[links(...)]`: `key = "/template"` for a plain URL, or
+// `key(href = "/template", title = "...", meta = EXPR, ...)` for a link object
+enum LinkAttr {
+    Url(syn::LitStr),
+    Object(Vec<(Ident, syn::Expr)>),
+}
+
+struct LinksAttr(Vec<(String, Span, LinkAttr)>);
 
-impl std::fmt::Display for ErrorMessage {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+impl syn::parse::Parse for LinksAttr {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut links = vec![];
+        while !input.is_empty() {
+            // Link keys like `self` are keywords, and custom keys can be given as strings
+            let (key, span) = if input.peek(syn::LitStr) {
+                let key = input.parse::<syn::LitStr>()?;
+                (key.value(), key.span())
+            } else {
+                let key = input.call(Ident::parse_any)?;
+                (key.to_string(), key.span())
+            };
+            let link = if input.peek(syn::token::Paren) {
+                let content;
+                syn::parenthesized!(content in input);
+                let mut members = vec![];
+                while !content.is_empty() {
+                    let member = content.call(Ident::parse_any)?;
+                    content.parse::<syn::Token![=]>()?;
+                    members.push((member, content.parse()?));
+                    if !content.is_empty() {
+                        content.parse::<syn::Token![,]>()?;
+                    }
+                }
+                LinkAttr::Object(members)
+            } else {
+                input.parse::<syn::Token![=]>()?;
+                LinkAttr::Url(input.parse()?)
+            };
+            links.push((key, span, link));
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+        Ok(LinksAttr(links))
     }
 }
 
-impl Error for ErrorMessage {}
+// A URL template like `/articles/{id}/comments`. Placeholders are replaced by the struct field of
+// the same name, `{id}` by the resource id if there is no such field, and are percent-encoded as
+// path segments. `{{` and `}}` are a literal `{` and `}`.
+struct UrlTemplate {
+    format: String,
+    arguments: Vec<proc_macro2::TokenStream>,
+    from_root: bool,
+}
 
-fn impl_linkify(ast: syn::DeriveInput) -> Result<proc_macro2::TokenStream, ErrorMessage> {
+impl UrlTemplate {
+    fn parse(template: &syn::LitStr, fields: &[Ident], krate: &syn::Path) -> syn::Result<Self> {
+        let value = template.value();
+        let mut format = String::new();
+        let mut arguments = vec![];
+        let mut rest = value.as_str();
+        while let Some(start) = rest.find(|c| c == '{' || c == '}') {
+            format.push_str(&rest[..start]);
+            // Escaped braces are kept escaped in the format string
+            if rest[start..].starts_with("{{") || rest[start..].starts_with("}}") {
+                format.push_str(&rest[start..start + 2]);
+                rest = &rest[start + 2..];
+                continue;
+            }
+            if &rest[start..=start] == "}" {
+                return Err(syn::Error::new(template.span(), "unmatched `}` in link"));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| syn::Error::new(template.span(), "unclosed `{` in link"))?;
+            let placeholder = rest[start + 1..start + end].trim();
+            let argument = match fields.iter().find(|field| *field == placeholder) {
+                Some(field) => quote! { #krate::export::PathSegment(&self.#field) },
+                None if placeholder == "id" => quote! {
+                    #krate::export::PathSegment(
+                        &#krate::export::IdSerialize(#krate::ResourceIdentifiable::get_id(self))
+                    )
+                },
+                None => {
+                    return Err(syn::Error::new(
+                        template.span(),
+                        format!("link refers to unknown field `{}`", placeholder),
+                    ))
+                }
+            };
+            format.push_str("{}");
+            arguments.push(argument);
+            rest = &rest[start + end + 1..];
+        }
+        format.push_str(rest);
+        Ok(UrlTemplate {
+            format,
+            arguments,
+            from_root: value.starts_with('/'),
+        })
+    }
+
+    // Expression of the URL, prefixed by the base URL of `context` if given and the template is
+    // a path from the server root
+    fn url(&self, context: Option<&Ident>) -> proc_macro2::TokenStream {
+        let arguments = &self.arguments;
+        match context {
+            Some(context) if self.from_root => {
+                let format = format!("{{}}{}", self.format);
+                quote! { format!(#format, #context.base_url(), #(#arguments),*) }
+            }
+            _ => {
+                let format = &self.format;
+                quote! { format!(#format, #(#arguments),*) }
+            }
+        }
+    }
+}
+
+// The URL template of the `href` of a link object
+fn href_template(span: Span, members: &[(Ident, syn::Expr)]) -> syn::Result<&syn::LitStr> {
+    let href = members
+        .iter()
+        .find(|(member, _)| member == "href")
+        .map(|(_, href)| href)
+        .ok_or_else(|| syn::Error::new(span, "link object requires `href`"))?;
+    match href {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: Str(template), ..
+        }) => Ok(template),
+        _ => Err(syn::Error::new_spanned(
+            href,
+            "`href` must be a string literal",
+        )),
+    }
+}
+
+fn link_tokens(
+    (key, span, link): &(String, Span, LinkAttr),
+    fields: &[Ident],
+    context: Option<&Ident>,
+    krate: &syn::Path,
+) -> syn::Result<proc_macro2::TokenStream> {
+    match link {
+        LinkAttr::Url(template) => {
+            let url = UrlTemplate::parse(template, fields, krate)?.url(context);
+            Ok(quote! { #krate::links::Link::url(#key, #url) })
+        }
+        LinkAttr::Object(members) => {
+            let url =
+                UrlTemplate::parse(href_template(*span, members)?, fields, krate)?.url(context);
+            let mut builder = vec![];
+            for (member, value) in members.iter().filter(|(member, _)| member != "href") {
+                let method = match member.to_string().as_str() {
+                    "rel" | "describedby" | "title" | "hreflang" | "meta" => member.clone(),
+                    "type" => Ident::new("media_type", member.span()),
+                    other => {
+                        return Err(syn::Error::new(
+                            member.span(),
+                            format!(
+                                "unknown link object member `{}`, expected one of `href`, `rel`, \
+                                 `describedby`, `title`, `type`, `hreflang` or `meta`",
+                                other
+                            ),
+                        ))
+                    }
+                };
+                // Link objects borrow their meta, from a constant or the fields of `self`
+                if member == "meta" {
+                    builder.push(quote! { .meta(&#value) });
+                } else {
+                    builder.push(quote! { .#method(#value) });
+                }
+            }
+            Ok(quote! {
+                #krate::links::Link::object(
+                    #key,
+                    #krate::links::LinkObject::new(#url)#(#builder)*,
+                )
+            })
+        }
+    }
+}
+
+fn impl_linkify(ast: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut attrs = ResourceAttrs::default();
+    for attr in ast.attrs.iter().filter(|attr| attr.path.is_ident("links")) {
+        let LinksAttr(links) = attr.parse_args::<LinksAttr>()?;
+        attrs.links.extend(links);
+    }
+    linkify_tokens(&ast, &attrs)
+}
+
+fn linkify_tokens(
+    ast: &syn::DeriveInput,
+    attrs: &ResourceAttrs,
+) -> syn::Result<proc_macro2::TokenStream> {
     let name = &ast.ident;
+    let krate = attrs.krate();
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    if attrs.links.is_empty() {
+        return Ok(quote! {
+            impl #impl_generics #krate::links::Linkify for #name #ty_generics #where_clause {
+                fn get_links(&self) -> Vec<#krate::links::Link> {
+                    vec![]
+                }
+
+                fn self_link_in(&self, _: &#krate::links::LinkContext) -> Option<String> {
+                    None
+                }
+            }
+        });
+    }
+    let fields = match &ast.data {
+        syn::Data::Struct(data_struct) => data_struct
+            .fields
+            .iter()
+            .filter_map(|field| field.ident.clone())
+            .collect(),
+        _ => vec![],
+    };
+    let context = Ident::new("context", Span::call_site());
+    let get_links = attrs
+        .links
+        .iter()
+        .map(|link| link_tokens(link, &fields, None, &krate))
+        .collect::<Result<Vec<_>, _>>()?;
+    let get_links_in = attrs
+        .links
+        .iter()
+        .map(|link| link_tokens(link, &fields, Some(&context), &krate))
+        .collect::<Result<Vec<_>, _>>()?;
+    // Only the `self` link is built when generating the links of relationships
+    let self_link_in = match attrs.links.iter().find(|(key, _, _)| key == "self") {
+        Some((_, span, link)) => {
+            let template = match link {
+                LinkAttr::Url(template) => template,
+                LinkAttr::Object(members) => href_template(*span, members)?,
+            };
+            let url = UrlTemplate::parse(template, &fields, &krate)?.url(Some(&context));
+            quote! { Some(#url) }
+        }
+        None => quote! { None },
+    };
     Ok(quote! {
-        impl rocket_jsonapi::links::Linkify for #name {
-            fn get_links(&self) -> Vec<rocket_jsonapi::links::Link> {
-                vec![]
+        impl #impl_generics #krate::links::Linkify for #name #ty_generics #where_clause {
+            fn get_links(&self) -> Vec<#krate::links::Link> {
+                vec![#(#get_links),*]
+            }
+
+            fn get_links_in(
+                &self,
+                #context: &#krate::links::LinkContext,
+            ) -> Vec<#krate::links::Link> {
+                vec![#(#get_links_in),*]
+            }
+
+            fn self_link_in(&self, #context: &#krate::links::LinkContext) -> Option<String> {
+                #self_link_in
             }
         }
     })
 }
 
-#[proc_macro_derive(Linkify)]
+#[proc_macro_derive(Linkify, attributes(links))]
 pub fn linkify_derive(input: TokenStream) -> TokenStream {
     expand_proc_macro(input, impl_linkify)
 }
 
-fn impl_relationships(ast: syn::DeriveInput) -> Result<proc_macro2::TokenStream, ErrorMessage> {
-    /*
-    let relationships = &ast
-        .attrs
-        .iter()
-        .filter_map(|attr| attr.parse_meta().ok())
-        .filter_map(|m| match m {
-            List(meta_list) => Some(meta_list),
+// `#[relationship(name = "author", to = Author)]` on the struct, for relationships implemented
+// through `HaveRelationship` by hand
+struct RelationshipAttr {
+    name: Option<syn::LitStr>,
+    to: Option<syn::Type>,
+}
+
+impl syn::parse::Parse for RelationshipAttr {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut name = None;
+        let mut to = None;
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+            if key == "name" {
+                name = Some(input.parse()?);
+            } else if key == "to" {
+                to = Some(input.parse()?);
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!(
+                        "unknown relationship key `{}`, expected `name` or `to`",
+                        key
+                    ),
+                ));
+            }
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+        Ok(RelationshipAttr { name, to })
+    }
+}
+
+impl RelationshipAttr {
+    // Both `name` and `to` are required, the error points at `tokens`
+    fn into_relationship<T: quote::ToTokens>(
+        self,
+        tokens: T,
+    ) -> syn::Result<(syn::LitStr, syn::Type)> {
+        match self {
+            RelationshipAttr {
+                name: Some(name),
+                to: Some(to),
+            } => Ok((name, to)),
+            _ => Err(syn::Error::new_spanned(
+                tokens,
+                "relationship requires both `name` and `to`",
+            )),
+        }
+    }
+}
+
+// Shape of a relationship field, decides how it is borrowed from the resource
+enum RelationField<'a> {
+    // `Option<To>`, a nullable to-one relationship
+    Optional(&'a syn::Type),
+    // `Vec<To>`, a to-many relationship
+    Many(&'a syn::Type),
+    // Any other type is a to-one relationship
+    One(&'a syn::Type),
+}
+
+impl<'a> RelationField<'a> {
+    fn from_type(ty: &'a syn::Type) -> Self {
+        let segment = match ty {
+            syn::Type::Path(type_path) if type_path.qself.is_none() => {
+                type_path.path.segments.last()
+            }
             _ => None,
-        })
-        .find(|m| m.path.is_ident("relationship"))
-        .map(|meta_list| {
-            let mut metas: Vec<syn::Meta> = Vec::new();
-            for nested_meta in meta_list.nested {
-                match nested_meta {
-                    syn::NestedMeta::Meta(meta) => metas.append(meta),
-                    _ => {}
+        };
+        let argument = segment.and_then(|segment| match &segment.arguments {
+            syn::PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+                match arguments.args.first() {
+                    Some(syn::GenericArgument::Type(argument)) => Some((&segment.ident, argument)),
+                    _ => None,
                 }
             }
+            _ => None,
         });
-    */
+        match argument {
+            Some((ident, argument)) if ident == "Option" => RelationField::Optional(argument),
+            Some((ident, argument)) if ident == "Vec" => RelationField::Many(argument),
+            _ => RelationField::One(ty),
+        }
+    }
+
+    // Type the relationship is borrowed as, with lifetime `lifetime`
+    fn relation_type(
+        &self,
+        lifetime: &syn::Lifetime,
+        krate: &syn::Path,
+    ) -> proc_macro2::TokenStream {
+        match self {
+            RelationField::Optional(to) => quote! { Option<&#lifetime #to> },
+            RelationField::Many(to) => {
+                quote! { #krate::response::JsonApiCollection<&#lifetime #to> }
+            }
+            RelationField::One(to) => quote! { &#lifetime #to },
+        }
+    }
+
+    fn relation(&self, field: &Ident, krate: &syn::Path) -> proc_macro2::TokenStream {
+        match self {
+            RelationField::Optional(_) => quote! { self.#field.as_ref() },
+            RelationField::Many(_) => quote! {
+                #krate::response::JsonApiCollection::data(self.#field.iter().collect())
+            },
+            RelationField::One(_) => quote! { &self.#field },
+        }
+    }
+
+    // Relationship object of the relation borrowed from `field`
+    fn relation_object(
+        &self,
+        field: &Ident,
+        name: &str,
+        krate: &syn::Path,
+    ) -> proc_macro2::TokenStream {
+        match self {
+            RelationField::Optional(_) => quote! {
+                #krate::relationship::RelationObject::to_one(#name, self.#field.as_ref())
+            },
+            RelationField::Many(_) => quote! {
+                #krate::relationship::RelationObject::to_many(#name, &self.#field)
+            },
+            RelationField::One(_) => quote! {
+                #krate::relationship::RelationObject::to_one(#name, Some(&self.#field))
+            },
+        }
+    }
+}
+
+// Name of the relationship of a field marked `#[jsonapi(relationship)]`, or
+// `#[jsonapi(relationship = "name")]` to rename it. `None` if the field is not a relationship
+fn field_relationship_name(field: &syn::Field) -> syn::Result<Option<String>> {
+    let mut relationship_name = None;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("jsonapi"))
+    {
+        let nested = match attr.parse_meta()? {
+            List(meta_list) => meta_list.nested,
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "expected `#[jsonapi(relationship)]`",
+                ))
+            }
+        };
+        for meta in nested {
+            match meta {
+                syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("relationship") => {
+                    relationship_name = field.ident.as_ref().map(|ident| ident.to_string());
+                }
+                syn::NestedMeta::Meta(NameValue(name_value))
+                    if name_value.path.is_ident("relationship") =>
+                {
+                    relationship_name = Some(string_value(&name_value.lit)?);
+                }
+                meta => {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        "unknown field attribute, expected `relationship`",
+                    ))
+                }
+            }
+        }
+    }
+    Ok(relationship_name)
+}
+
+// Value of a string literal, or an error pointing at a literal of any other type
+fn string_value(lit: &syn::Lit) -> syn::Result<String> {
+    match lit {
+        Str(literal) => Ok(literal.value()),
+        _ => Err(syn::Error::new_spanned(lit, "expected a string literal")),
+    }
+}
+
+// The path of `crate = "..."`
+fn crate_path(literal: &syn::LitStr) -> syn::Result<syn::Path> {
+    literal
+        .parse()
+        .map_err(|_| syn::Error::new(literal.span(), "crate must be a valid path"))
+}
+
+// The string value of the struct attribute `#[name = "..."]`, if given
+fn name_value_attr(attrs: &[syn::Attribute], name: &str) -> syn::Result<Option<syn::LitStr>> {
+    let mut value = None;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident(name)) {
+        match attr.parse_meta()? {
+            NameValue(syn::MetaNameValue {
+                lit: Str(literal), ..
+            }) => value = Some(literal),
+            NameValue(name_value) => {
+                return Err(syn::Error::new_spanned(
+                    name_value.lit,
+                    "expected a string literal",
+                ))
+            }
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    format!("expected `#[{} = \"...\"]`", name),
+                ))
+            }
+        }
+    }
+    Ok(value)
+}
+
+fn impl_relationships(ast: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut attrs = ResourceAttrs::default();
+    for attr in ast
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("relationship"))
+    {
+        let relationship = attr.parse_args::<RelationshipAttr>()?;
+        attrs
+            .relationships
+            .push(relationship.into_relationship(attr)?);
+    }
+    relationships_tokens(&ast, &attrs)
+}
+
+fn relationships_tokens(
+    ast: &syn::DeriveInput,
+    attrs: &ResourceAttrs,
+) -> syn::Result<proc_macro2::TokenStream> {
     let name = &ast.ident;
+    let krate = attrs.krate();
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let mut relation_objects = vec![];
+    for (name, to) in &attrs.relationships {
+        relation_objects.push(quote! {
+            <Self as #krate::relationship::RelationObjectify<#to>>::get_relation_object(
+                self, #name,
+            )
+        });
+    }
+    let mut have_relationships = vec![];
+    if let syn::Data::Struct(syn::DataStruct {
+        fields: syn::Fields::Named(fields),
+        ..
+    }) = &ast.data
+    {
+        // Named so it can not clash with the lifetimes of the resource
+        let lifetime = syn::Lifetime::new("'__relation", Span::call_site());
+        let mut relation_generics = ast.generics.clone();
+        relation_generics.params.insert(
+            0,
+            syn::GenericParam::Lifetime(syn::LifetimeDef::new(lifetime.clone())),
+        );
+        let (relation_impl_generics, _, _) = relation_generics.split_for_impl();
+        for field in &fields.named {
+            let relation_name = match field_relationship_name(field)? {
+                Some(relation_name) => relation_name,
+                None => continue,
+            };
+            let field_name = field.ident.as_ref().unwrap();
+            let relation_field = RelationField::from_type(&field.ty);
+            let relation_type = relation_field.relation_type(&lifetime, &krate);
+            let relation = relation_field.relation(field_name, &krate);
+            have_relationships.push(quote! {
+                impl #relation_impl_generics #krate::relationship::HaveRelationship<#lifetime, #relation_type>
+                    for #name #ty_generics #where_clause
+                {
+                    fn get_relation(&#lifetime self) -> #relation_type {
+                        #relation
+                    }
+                }
+            });
+            relation_objects.push(relation_field.relation_object(
+                field_name,
+                &relation_name,
+                &krate,
+            ));
+        }
+    }
     Ok(quote! {
-        impl rocket_jsonapi::relationship::Relationships for #name {
-            fn get_all_relation_objects() -> Vec<rocket_jsonapi::relationship::RelationObject> {
-                vec![]
+        #(#have_relationships)*
+
+        impl #impl_generics #krate::relationship::Relationships for #name #ty_generics #where_clause {
+            fn get_all_relation_objects(&self) -> Vec<#krate::relationship::RelationObject> {
+                vec![#(#relation_objects),*]
             }
         }
     })
 }
 
-#[proc_macro_derive(Relationships, attributes(relationships))]
+#[proc_macro_derive(Relationships, attributes(relationship, jsonapi))]
 pub fn relationships_derive(input: TokenStream) -> TokenStream {
     expand_proc_macro(input, impl_relationships)
 }
 
-// TODO refactor, better names
 fn ident_id() -> Ident {
     Ident::new("id", Span::call_site())
 }
 
-fn ident_type_from(name: &Ident) -> Ident {
-    Ident::new(&format!("{}", name), name.span())
+// Case convention for `#[resource_ident_type(rename_all = "...")]`
+enum RenameRule {
+    Kebab,
+    Snake,
+    Camel,
 }
 
-// TODO pretty errors something like this
-/*
-#[derive(Debug)]
-enum ResourceIdentifiableDeriveError {
-    NotNamedStruct,
-    NotStruct,
-    InvalidStruct {
-        struct_name: String,
-        field_name: String,
-    },
+impl RenameRule {
+    fn from_lit(literal: &syn::LitStr) -> syn::Result<Self> {
+        match literal.value().as_str() {
+            "kebab-case" => Ok(RenameRule::Kebab),
+            "snake_case" => Ok(RenameRule::Snake),
+            "camelCase" => Ok(RenameRule::Camel),
+            rule => Err(syn::Error::new(
+                literal.span(),
+                format!(
+                    "unknown rename rule `{}`, expected `kebab-case`, `snake_case` or `camelCase`",
+                    rule
+                ),
+            )),
+        }
+    }
+
+    fn apply(&self, words: &[String]) -> String {
+        match self {
+            RenameRule::Kebab => words.join("-").to_lowercase(),
+            RenameRule::Snake => words.join("_").to_lowercase(),
+            RenameRule::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(i, word)| {
+                    let word = word.to_lowercase();
+                    if i == 0 {
+                        word
+                    } else {
+                        let mut chars = word.chars();
+                        chars
+                            .next()
+                            .map(|first| first.to_uppercase().chain(chars).collect())
+                            .unwrap_or_default()
+                    }
+                })
+                .collect(),
+        }
+    }
 }
 
-impl fmt::Display for ResourceIdentifiableDeriveError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match &self {
-                ResourceIdentifiableDeriveError::NotNamedStruct => "TODO1",
-                ResourceIdentifiableDeriveError::NotStruct => "TODO2",
-                ResourceIdentifiableDeriveError::InvalidStruct {
-                    ref struct_name,
-                    ref field_name,
-                } => "TODO3",
-            }
-        )
+// Splits a struct name like `BlogPost` or `HTTPRequest` into its words
+fn split_words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words: Vec<String> = vec![];
+    for (i, c) in chars.iter().enumerate() {
+        let starts_word = i > 0
+            && c.is_uppercase()
+            && (!chars[i - 1].is_uppercase()
+                || chars.get(i + 1).map_or(false, |next| next.is_lowercase()));
+        match words.last_mut() {
+            Some(word) if !starts_word && *c != '_' => word.push(*c),
+            _ if *c == '_' => words.push(String::new()),
+            _ => words.push(c.to_string()),
+        }
     }
+    words.retain(|word| !word.is_empty());
+    words
 }
 
-impl Error for ResourceIdentifiableDeriveError {}
-*/
+// English plural of a single word, good enough for resource names
+fn pluralize(word: &str) -> String {
+    let lower = word.to_lowercase();
+    if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|suffix| lower.ends_with(suffix))
+    {
+        format!("{}es", word)
+    } else if lower.ends_with('y') && !lower[..lower.len() - 1].ends_with(|c| "aeiou".contains(c)) {
+        format!("{}ies", &word[..word.len() - 1])
+    } else {
+        format!("{}s", word)
+    }
+}
 
-fn impl_resource_type(ast: syn::DeriveInput) -> Result<proc_macro2::TokenStream, ErrorMessage> {
-    let name = &ast.ident;
-    let name_values = &ast
+// Options of a resource. The separate derives read them from their own attributes, and
+// `JsonApiResource` from `#[jsonapi(...)]`
+#[derive(Default)]
+struct ResourceAttrs {
+    krate: Option<syn::Path>,
+    resource_type: Option<syn::LitStr>,
+    rename_rule: Option<RenameRule>,
+    plural: bool,
+    id: Option<syn::LitStr>,
+    type_field: Option<syn::LitStr>,
+    links: Vec<(String, Span, LinkAttr)>,
+    relationships: Vec<(syn::LitStr, syn::Type)>,
+    meta: Vec<(String, syn::Expr)>,
+    transparent: bool,
+}
+
+impl ResourceAttrs {
+    // The options of all `#[jsonapi(...)]` attributes on the struct
+    fn from_jsonapi(ast: &syn::DeriveInput) -> syn::Result<Self> {
+        let mut attrs = ResourceAttrs::default();
+        for attr in ast
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("jsonapi"))
+        {
+            attr.parse_args_with(|input: syn::parse::ParseStream| attrs.parse_jsonapi(input))?;
+        }
+        Ok(attrs)
+    }
+
+    // Path to `rocket_jsonapi` in the generated code, `crate = "..."` for crates re-exporting it
+    fn krate(&self) -> syn::Path {
+        self.krate
+            .clone()
+            .unwrap_or_else(|| syn::parse_quote!(rocket_jsonapi))
+    }
+
+    // The given resource type, or the struct name after `rename_all` and `plural`
+    fn resource_type(&self, name: &Ident) -> String {
+        if let Some(resource_type) = &self.resource_type {
+            return resource_type.value();
+        }
+        let name = name.unraw().to_string();
+        let name = if self.plural { pluralize(&name) } else { name };
+        match &self.rename_rule {
+            Some(rule) => rule.apply(&split_words(&name)),
+            None => name,
+        }
+    }
+
+    // `#[resource_ident_type = "..."]` or `#[resource_ident_type(rename_all = "...", plural)]`
+    fn parse_resource_ident_type(&mut self, attr: &syn::Attribute) -> syn::Result<()> {
+        match attr.parse_meta()? {
+            NameValue(syn::MetaNameValue {
+                lit: Str(literal), ..
+            }) => self.resource_type = Some(literal),
+            NameValue(name_value) => {
+                return Err(syn::Error::new_spanned(
+                    name_value.lit,
+                    "expected a string literal",
+                ))
+            }
+            List(list) => {
+                for nested in list.nested.iter() {
+                    match nested {
+                        syn::NestedMeta::Meta(NameValue(syn::MetaNameValue {
+                            path,
+                            lit: Str(literal),
+                            ..
+                        })) if path.is_ident("rename_all") => {
+                            self.rename_rule = Some(RenameRule::from_lit(literal)?)
+                        }
+                        syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("plural") => {
+                            self.plural = true
+                        }
+                        nested => {
+                            return Err(syn::Error::new_spanned(
+                                nested,
+                                "unknown resource type option, expected `rename_all = \"...\"` or `plural`",
+                            ))
+                        }
+                    }
+                }
+            }
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "expected `#[resource_ident_type = \"...\"]`",
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    // The contents of `#[jsonapi(...)]` on the struct
+    fn parse_jsonapi(&mut self, input: syn::parse::ParseStream) -> syn::Result<()> {
+        while !input.is_empty() {
+            // `type` and `crate` are keywords
+            let key = input.call(Ident::parse_any)?;
+            match key.to_string().as_str() {
+                "crate" => {
+                    input.parse::<syn::Token![=]>()?;
+                    self.krate = Some(crate_path(&input.parse()?)?);
+                }
+                "type" => {
+                    input.parse::<syn::Token![=]>()?;
+                    self.resource_type = Some(input.parse()?);
+                }
+                "rename_all" => {
+                    input.parse::<syn::Token![=]>()?;
+                    self.rename_rule = Some(RenameRule::from_lit(&input.parse()?)?);
+                }
+                "plural" => self.plural = true,
+                "transparent" => self.transparent = true,
+                "id" => {
+                    input.parse::<syn::Token![=]>()?;
+                    self.id = Some(input.parse()?);
+                }
+                "type_field" => {
+                    input.parse::<syn::Token![=]>()?;
+                    self.type_field = Some(input.parse()?);
+                }
+                "links" => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    let LinksAttr(links) = content.parse()?;
+                    self.links.extend(links);
+                }
+                "relationship" => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    let relationship = content.parse::<RelationshipAttr>()?;
+                    self.relationships
+                        .push(relationship.into_relationship(&key)?);
+                }
+                "meta" => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    while !content.is_empty() {
+                        // Members can be given as strings, for names that are not identifiers
+                        let member = if content.peek(syn::LitStr) {
+                            content.parse::<syn::LitStr>()?.value()
+                        } else {
+                            content.call(Ident::parse_any)?.to_string()
+                        };
+                        content.parse::<syn::Token![=]>()?;
+                        self.meta.push((member, content.parse()?));
+                        if !content.is_empty() {
+                            content.parse::<syn::Token![,]>()?;
+                        }
+                    }
+                }
+                other => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!(
+                            "unknown jsonapi attribute `{}`, expected one of `type`, `rename_all`, \
+                             `plural`, `id`, `type_field`, `links`, `relationship`, `meta`, \
+                             `transparent` or `crate`",
+                            other
+                        ),
+                    ))
+                }
+            }
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn impl_resource_type(ast: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let mut attrs = ResourceAttrs::default();
+    for attr in ast
         .attrs
         .iter()
-        .filter_map(|attr| attr.parse_meta().ok())
-        .filter_map(|m| match m {
-            NameValue(meta) => Some(meta),
-            _ => None,
-        })
-        .collect::<Vec<MetaNameValue>>();
-    // TODO refactor, maybe share with `resource_ident_id`
-    let resource_ident_type = name_values
-        .iter()
-        .find(|m| m.path.is_ident("resource_ident_type"))
-        .map_or(ident_type_from(&name), |m| match &m.lit {
-            Str(literal) => Ident::new(&literal.value(), Span::call_site()),
-            _ => ident_type_from(&name),
-        });
-    // Defining inner macro for each expansion is ugly
+        .filter(|attr| attr.path.is_ident("resource_ident_type"))
+    {
+        attrs.parse_resource_ident_type(attr)?;
+    }
+    resource_type_tokens(&ast, &attrs)
+}
+
+fn resource_type_tokens(
+    ast: &syn::DeriveInput,
+    attrs: &ResourceAttrs,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+    let krate = attrs.krate();
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let resource_type = syn::LitStr::new(&attrs.resource_type(name), name.span());
     let gen = quote! {
-        impl rocket_jsonapi::ResourceType for #name {
+        impl #impl_generics #krate::ResourceType for #name #ty_generics #where_clause {
             fn get_type() -> &'static str {
-                &stringify!(#resource_ident_type)
+                #resource_type
             }
         }
     };
     Ok(gen)
 }
 
-fn impl_resource_identifiable(
-    ast: syn::DeriveInput,
-) -> Result<proc_macro2::TokenStream, ErrorMessage> {
+fn impl_resource_identifiable(ast: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let attrs = ResourceAttrs {
+        id: name_value_attr(&ast.attrs, "resource_ident_id")?,
+        ..ResourceAttrs::default()
+    };
+    resource_identifiable_tokens(&ast, &attrs)
+}
+
+fn resource_identifiable_tokens(
+    ast: &syn::DeriveInput,
+    attrs: &ResourceAttrs,
+) -> syn::Result<proc_macro2::TokenStream> {
     let name = &ast.ident;
-    let name_values = &ast
-        .attrs
-        .iter()
-        .filter_map(|attr| attr.parse_meta().ok())
-        .filter_map(|m| match m {
-            NameValue(meta) => Some(meta),
-            _ => None,
-        })
-        .collect::<Vec<MetaNameValue>>();
-    // TODO refactor, maybe share with `resource_ident_type`
-    let resource_ident_id = name_values
+    let krate = attrs.krate();
+    let resource_ident_id = match &attrs.id {
+        Some(literal) => literal
+            .parse::<Ident>()
+            .map_err(|_| syn::Error::new(literal.span(), "id field must be a valid identifier"))?,
+        None => ident_id(),
+    };
+    let fields = match &ast.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => fields,
+        syn::Data::Struct(_) => {
+            return Err(syn::Error::new(
+                name.span(),
+                "ResourceIdentifiable must be derived from a struct with named fields",
+            ))
+        }
+        _ => {
+            return Err(syn::Error::new(
+                name.span(),
+                "ResourceIdentifiable must be derived from a struct",
+            ))
+        }
+    };
+    let id_field = fields
+        .named
         .iter()
-        .find(|m| m.path.is_ident("resource_ident_id"))
-        .map_or(ident_id(), |m| match &m.lit {
-            Str(literal) => Ident::new(&literal.value(), Span::call_site()),
-            _ => ident_id(),
-        });
-    let id_field = match &ast.data {
-        syn::Data::Struct(data_struct) => match &data_struct.fields {
-            syn::Fields::Named(fields) => Ok(fields),
-            _ => Err(ErrorMessage(
-                "ResourceIdentifiable must be derived from a named struct".to_string(),
-            )),
-        },
-        _ => Err(ErrorMessage(
-            "ResourceIdentifiable must be derived from a struct".to_string(),
-        )),
-    }?
-    .named
-    .iter()
-    .find(|f| {
-        f.ident
-            .as_ref()
-            .map(|i| i.eq(&resource_ident_id))
-            .eq(&Some(true))
-    })
-    .ok_or_else(|| {
-        ErrorMessage(format!(
-            "{} does not have an id field named {}",
-            name, resource_ident_id
-        ))
-    })?;
+        .find(|f| f.ident.as_ref() == Some(&resource_ident_id))
+        .ok_or_else(|| {
+            let message = format!(
+                "{} does not have an id field named `{}`",
+                name, resource_ident_id
+            );
+            match &attrs.id {
+                Some(literal) => syn::Error::new(literal.span(), message),
+                None => syn::Error::new(name.span(), message),
+            }
+        })?;
     let id_type = &id_field.ty;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
     // Defining inner macro for each expansion is ugly
     let gen = quote! {
-        impl rocket_jsonapi::ResourceIdentifiable for #name {
+        impl #impl_generics #krate::ResourceIdentifiable for #name #ty_generics #where_clause {
             type IdType = #id_type;
 
             fn get_id(&self) -> &Self::IdType {
@@ -204,6 +932,469 @@ fn impl_resource_identifiable(
     Ok(gen)
 }
 
+// `ResourceMeta` with a `Meta` map of the members of `meta(...)`, nothing if there are none
+fn resource_meta_tokens(ast: &syn::DeriveInput, attrs: &ResourceAttrs) -> proc_macro2::TokenStream {
+    if attrs.meta.is_empty() {
+        return quote! {};
+    }
+    let name = &ast.ident;
+    let krate = attrs.krate();
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let members = attrs
+        .meta
+        .iter()
+        .map(|(member, value)| quote! { .with(#member, #value) });
+    quote! {
+        impl #impl_generics #krate::resource::ResourceMeta for #name #ty_generics #where_clause {
+            type Meta = #krate::resource::Meta;
+
+            fn get_meta(&self) -> Self::Meta {
+                #krate::resource::Meta::new()#(#members)*
+            }
+        }
+    }
+}
+
+// `ForwardResourceMeta` serializing the meta of the wrapped resource, if it has any. `serialize`
+// serializes it into `state` with `MaybeResourceMeta`
+fn forward_resource_meta_tokens(
+    ast: &syn::DeriveInput,
+    attrs: &ResourceAttrs,
+    serialize: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let name = &ast.ident;
+    let krate = attrs.krate();
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    quote! {
+        impl #impl_generics #krate::export::ForwardResourceMeta for #name #ty_generics #where_clause {
+            fn forward_meta<S: #krate::export::SerializeStruct>(
+                &self,
+                state: &mut S,
+            ) -> Result<(), S::Error> {
+                #serialize
+            }
+        }
+    }
+}
+
+// `InstanceType` reading the type of each instance from the field of `type_field = "..."`, like
+// the type column of single table inheritance. Nothing if there is no type field
+fn instance_type_tokens(
+    ast: &syn::DeriveInput,
+    attrs: &ResourceAttrs,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let literal = match &attrs.type_field {
+        Some(literal) => literal,
+        None => return Ok(quote! {}),
+    };
+    let name = &ast.ident;
+    let krate = attrs.krate();
+    let type_field = literal
+        .parse::<Ident>()
+        .map_err(|_| syn::Error::new(literal.span(), "type field must be a valid identifier"))?;
+    let has_field = match &ast.data {
+        syn::Data::Struct(data_struct) => data_struct
+            .fields
+            .iter()
+            .any(|f| f.ident.as_ref() == Some(&type_field)),
+        _ => false,
+    };
+    if !has_field {
+        return Err(syn::Error::new(
+            literal.span(),
+            format!("{} does not have a type field named `{}`", name, type_field),
+        ));
+    }
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #krate::resource::InstanceType for #name #ty_generics #where_clause {
+            fn instance_type(&self) -> &str {
+                ::std::convert::AsRef::<str>::as_ref(&self.#type_field)
+            }
+        }
+    })
+}
+
+// The single field of a `transparent` resource, as its member and its type
+fn transparent_field(ast: &syn::DeriveInput) -> syn::Result<(syn::Member, &syn::Type)> {
+    let fields = match &ast.data {
+        syn::Data::Struct(data_struct) => &data_struct.fields,
+        _ => {
+            return Err(syn::Error::new(
+                ast.ident.span(),
+                "transparent resources must be structs",
+            ))
+        }
+    };
+    let mut iter = fields.iter();
+    match (iter.next(), iter.next()) {
+        (Some(field), None) => {
+            let member = match &field.ident {
+                Some(ident) => syn::Member::Named(ident.clone()),
+                None => syn::Member::Unnamed(syn::Index::from(0)),
+            };
+            Ok((member, &field.ty))
+        }
+        _ => Err(syn::Error::new(
+            ast.ident.span(),
+            "transparent resources must have exactly one field",
+        )),
+    }
+}
+
+// A newtype over a resource, which forwards everything that is not overridden to its field
+fn transparent_tokens(
+    ast: &syn::DeriveInput,
+    attrs: &ResourceAttrs,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if let Some(id) = &attrs.id {
+        return Err(syn::Error::new(
+            id.span(),
+            "the id of a transparent resource is the id of its field",
+        ));
+    }
+    if let Some(type_field) = &attrs.type_field {
+        return Err(syn::Error::new(
+            type_field.span(),
+            "the type of a transparent resource is the type of its field",
+        ));
+    }
+    let name = &ast.ident;
+    let krate = attrs.krate();
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let (member, inner) = transparent_field(ast)?;
+    // Without a type of its own, instances have the type of the instance of their field
+    let resource_type = if attrs.resource_type.is_some()
+        || attrs.rename_rule.is_some()
+        || attrs.plural
+    {
+        resource_type_tokens(ast, attrs)?
+    } else {
+        quote! {
+            impl #impl_generics #krate::ResourceType for #name #ty_generics #where_clause {
+                fn get_type() -> &'static str {
+                    <#inner as #krate::ResourceType>::get_type()
+                }
+            }
+
+            impl #impl_generics #krate::resource::InstanceType for #name #ty_generics #where_clause {
+                fn instance_type(&self) -> &str {
+                    #krate::export::MaybeInstanceType::type_of(&self.#member)
+                }
+            }
+        }
+    };
+    let linkify = if !attrs.links.is_empty() {
+        linkify_tokens(ast, attrs)?
+    } else {
+        quote! {
+            impl #impl_generics #krate::links::Linkify for #name #ty_generics #where_clause {
+                fn get_links(&self) -> Vec<#krate::links::Link> {
+                    #krate::links::Linkify::get_links(&self.#member)
+                }
+
+                fn get_links_in(
+                    &self,
+                    context: &#krate::links::LinkContext,
+                ) -> Vec<#krate::links::Link> {
+                    #krate::links::Linkify::get_links_in(&self.#member, context)
+                }
+
+                fn self_link_in(&self, context: &#krate::links::LinkContext) -> Option<String> {
+                    #krate::links::Linkify::self_link_in(&self.#member, context)
+                }
+            }
+        }
+    };
+    // The field might not have relationships, which is only known after specialization
+    let relationships = if !attrs.relationships.is_empty() {
+        relationships_tokens(ast, attrs)?
+    } else {
+        quote! {
+            impl #impl_generics #krate::relationship::Relationships for #name #ty_generics #where_clause {
+                fn get_all_relation_objects(&self) -> Vec<#krate::relationship::RelationObject> {
+                    #krate::export::MaybeRelationships::relation_objects(&self.#member)
+                }
+            }
+        }
+    };
+    // Like relationships, whether the field has meta is only known after specialization
+    let resource_meta = if !attrs.meta.is_empty() {
+        resource_meta_tokens(ast, attrs)
+    } else {
+        forward_resource_meta_tokens(
+            ast,
+            attrs,
+            quote! { #krate::export::MaybeResourceMeta::serialize_meta(&self.#member, state) },
+        )
+    };
+    Ok(quote! {
+        #resource_type
+
+        impl #impl_generics #krate::ResourceIdentifiable for #name #ty_generics #where_clause {
+            type IdType = <#inner as #krate::ResourceIdentifiable>::IdType;
+
+            fn get_id(&self) -> &Self::IdType {
+                #krate::ResourceIdentifiable::get_id(&self.#member)
+            }
+        }
+
+        #linkify
+        #relationships
+        #resource_meta
+
+        impl #impl_generics #krate::export::Serialize for #name #ty_generics #where_clause {
+            fn serialize<__S>(&self, serializer: __S) -> Result<__S::Ok, __S::Error>
+            where
+                __S: #krate::export::Serializer,
+            {
+                #krate::export::Serialize::serialize(&self.#member, serializer)
+            }
+        }
+    })
+}
+
+// The variants of an enum of resources, each holding a single resource. Returns a pattern for
+// each variant binding its resource to `resource`, which can also construct the variant, and the
+// type of its resource
+fn variant_resources<'a>(
+    ast: &syn::DeriveInput,
+    data_enum: &'a syn::DataEnum,
+) -> syn::Result<(Vec<proc_macro2::TokenStream>, Vec<&'a syn::Type>)> {
+    let name = &ast.ident;
+    let mut patterns = vec![];
+    let mut types = vec![];
+    for variant in &data_enum.variants {
+        let variant_name = &variant.ident;
+        let mut fields = variant.fields.iter();
+        let field = match (fields.next(), fields.next()) {
+            (Some(field), None) => field,
+            _ => {
+                return Err(syn::Error::new(
+                    variant_name.span(),
+                    "variants of a resource enum must hold exactly one resource",
+                ))
+            }
+        };
+        patterns.push(match &field.ident {
+            Some(ident) => quote! { #name::#variant_name { #ident: resource } },
+            None => quote! { #name::#variant_name(resource) },
+        });
+        types.push(&field.ty);
+    }
+    if types.is_empty() {
+        return Err(syn::Error::new(
+            name.span(),
+            "a resource enum must have at least one variant",
+        ));
+    }
+    Ok((patterns, types))
+}
+
+// Sets the span of all tokens, so errors in the generated code point at the given span
+fn respan(tokens: proc_macro2::TokenStream, span: Span) -> proc_macro2::TokenStream {
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            if let proc_macro2::TokenTree::Group(group) = &token {
+                let mut respanned =
+                    proc_macro2::Group::new(group.delimiter(), respan(group.stream(), span));
+                respanned.set_span(span);
+                token = respanned.into();
+            }
+            token.set_span(span);
+            token
+        })
+        .collect()
+}
+
+// An enum of resources of several types, each variant holding a single resource. Instances are
+// resources of the type of the resource they hold
+fn enum_tokens(
+    ast: &syn::DeriveInput,
+    data_enum: &syn::DataEnum,
+    attrs: &ResourceAttrs,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let unsupported = attrs
+        .id
+        .as_ref()
+        .or_else(|| attrs.type_field.as_ref())
+        .map(|literal| literal.span())
+        .or_else(|| attrs.links.first().map(|(_, span, _)| *span))
+        .or_else(|| attrs.relationships.first().map(|(name, _)| name.span()))
+        .or_else(|| attrs.meta.first().map(|(_, value)| value.span()));
+    if let Some(span) = unsupported {
+        return Err(syn::Error::new(
+            span,
+            "enums only support `type`, `rename_all`, `plural` and `crate`, the rest is given by \
+             the resources of their variants",
+        ));
+    }
+    let name = &ast.ident;
+    let krate = attrs.krate();
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let (patterns, types) = variant_resources(ast, data_enum)?;
+    // All variants have the id type of the first variant, a variant with another id type is the
+    // span of the error
+    let id_type = types[0];
+    let variant_ids = data_enum.variants.iter().map(|variant| {
+        respan(
+            quote! { #krate::export::SameIdType::<#id_type>::first_id(resource) },
+            variant.ident.span(),
+        )
+    });
+    let resource_type = resource_type_tokens(ast, attrs)?;
+    let resource_meta = forward_resource_meta_tokens(
+        ast,
+        attrs,
+        quote! {
+            match self {
+                #(#patterns => #krate::export::MaybeResourceMeta::serialize_meta(resource, state),)*
+            }
+        },
+    );
+    Ok(quote! {
+        #resource_type
+        #resource_meta
+
+        impl #impl_generics #krate::resource::InstanceType for #name #ty_generics #where_clause {
+            fn instance_type(&self) -> &str {
+                match self {
+                    #(#patterns => #krate::export::MaybeInstanceType::type_of(resource),)*
+                }
+            }
+        }
+
+        impl #impl_generics #krate::ResourceIdentifiable for #name #ty_generics #where_clause {
+            type IdType = <#id_type as #krate::ResourceIdentifiable>::IdType;
+
+            fn get_id(&self) -> &Self::IdType {
+                match self {
+                    #(#patterns => #variant_ids,)*
+                }
+            }
+        }
+
+        impl #impl_generics #krate::links::Linkify for #name #ty_generics #where_clause {
+            fn get_links(&self) -> Vec<#krate::links::Link> {
+                match self {
+                    #(#patterns => #krate::links::Linkify::get_links(resource),)*
+                }
+            }
+
+            fn get_links_in(
+                &self,
+                context: &#krate::links::LinkContext,
+            ) -> Vec<#krate::links::Link> {
+                match self {
+                    #(#patterns => #krate::links::Linkify::get_links_in(resource, context),)*
+                }
+            }
+
+            fn self_link_in(&self, context: &#krate::links::LinkContext) -> Option<String> {
+                match self {
+                    #(#patterns => #krate::links::Linkify::self_link_in(resource, context),)*
+                }
+            }
+        }
+
+        impl #impl_generics #krate::relationship::Relationships for #name #ty_generics #where_clause {
+            fn get_all_relation_objects(&self) -> Vec<#krate::relationship::RelationObject> {
+                match self {
+                    #(#patterns => #krate::export::MaybeRelationships::relation_objects(resource),)*
+                }
+            }
+        }
+
+        impl #impl_generics #krate::export::Serialize for #name #ty_generics #where_clause {
+            fn serialize<__S>(&self, serializer: __S) -> Result<__S::Ok, __S::Error>
+            where
+                __S: #krate::export::Serializer,
+            {
+                match self {
+                    #(#patterns => #krate::export::Serialize::serialize(resource, serializer),)*
+                }
+            }
+        }
+    })
+}
+
+fn impl_jsonapi_resource(ast: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let attrs = ResourceAttrs::from_jsonapi(&ast)?;
+    if attrs.transparent {
+        return transparent_tokens(&ast, &attrs);
+    }
+    if let syn::Data::Enum(data_enum) = &ast.data {
+        return enum_tokens(&ast, data_enum, &attrs);
+    }
+    let resource_type = resource_type_tokens(&ast, &attrs)?;
+    let resource_identifiable = resource_identifiable_tokens(&ast, &attrs)?;
+    let linkify = linkify_tokens(&ast, &attrs)?;
+    let relationships = relationships_tokens(&ast, &attrs)?;
+    let instance_type = instance_type_tokens(&ast, &attrs)?;
+    let resource_meta = resource_meta_tokens(&ast, &attrs);
+    Ok(quote! {
+        #resource_type
+        #instance_type
+        #resource_identifiable
+        #linkify
+        #relationships
+        #resource_meta
+    })
+}
+
+// `InputResource` of an enum of input types, deserializing the variant whose type is accepted. Single
+// input types implement it through `ResourceType` and `Deserialize`
+fn impl_input_resource(ast: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+    // Shares `#[jsonapi(...)]` with `JsonApiResource`, only `crate = "..."` is used here
+    let krate = ResourceAttrs::from_jsonapi(&ast)?.krate();
+    let data_enum = match &ast.data {
+        syn::Data::Enum(data_enum) => data_enum,
+        _ => {
+            return Err(syn::Error::new(
+                name.span(),
+                "InputResource is derived on enums of input types, structs implement it by \
+                 deriving ResourceType and Deserialize",
+            ))
+        }
+    };
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let (variants, types) = variant_resources(&ast, data_enum)?;
+    Ok(quote! {
+        impl #impl_generics #krate::InputResource for #name #ty_generics #where_clause {
+            fn input_types() -> Vec<&'static str> {
+                let mut input_types = Vec::new();
+                #(input_types.extend(<#types as #krate::InputResource>::input_types());)*
+                input_types
+            }
+
+            fn accepts_type(resource_type: &str) -> bool {
+                #(<#types as #krate::InputResource>::accepts_type(resource_type))||*
+            }
+
+            fn deserialize_input<'de, __D>(
+                resource_type: &str,
+                attributes: __D,
+            ) -> Result<Self, __D::Error>
+            where
+                __D: #krate::export::Deserializer<'de>,
+            {
+                #(
+                    if <#types as #krate::InputResource>::accepts_type(resource_type) {
+                        let resource = <#types as #krate::InputResource>::deserialize_input(
+                            resource_type,
+                            attributes,
+                        )?;
+                        return Ok(#variants);
+                    }
+                )*
+                Err(#krate::export::unaccepted_type::<Self, __D::Error>(resource_type))
+            }
+        }
+    })
+}
+
 #[proc_macro_derive(ResourceType, attributes(resource_ident_type))]
 pub fn resource_type_derive(input: TokenStream) -> TokenStream {
     expand_proc_macro(input, impl_resource_type)
@@ -214,17 +1405,146 @@ pub fn resource_identifiable_derive(input: TokenStream) -> TokenStream {
     expand_proc_macro(input, impl_resource_identifiable)
 }
 
-// Thanks to diesel
-fn expand_proc_macro<T: syn::parse::Parse, E: Error>(
-    input: TokenStream,
-    f: fn(T) -> Result<proc_macro2::TokenStream, E>,
-) -> TokenStream {
-    let item = syn::parse(input).unwrap();
-    match f(item) {
-        Ok(x) => x.into(),
-        Err(e) => {
-            panic!("{}", e);
-            "".parse().unwrap()
+// `ResourceId` of an id made of the fields of a struct, like a composite key. The fields are joined
+// by a separator, `-` unless changed with `#[resource_id(separator = "...")]`. `crate = "..."` sets
+// the path of `rocket_jsonapi` like for `JsonApiResource`
+fn impl_resource_id(ast: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+    let mut krate: syn::Path = syn::parse_quote!(rocket_jsonapi);
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let mut separator = "-".to_string();
+    for attr in ast
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("resource_id"))
+    {
+        match attr.parse_meta()? {
+            List(list) => {
+                for nested in list.nested.iter() {
+                    match nested {
+                        syn::NestedMeta::Meta(NameValue(name_value))
+                            if name_value.path.is_ident("separator") =>
+                        {
+                            separator = string_value(&name_value.lit)?;
+                            if separator.is_empty() {
+                                return Err(syn::Error::new_spanned(
+                                    &name_value.lit,
+                                    "separator must not be empty",
+                                ));
+                            }
+                            // Escaped parts are made of `%` and hex digits besides their own
+                            // characters, so these would split an escape when parsing
+                            if separator.chars().any(|c| c == '%' || c.is_ascii_hexdigit()) {
+                                return Err(syn::Error::new_spanned(
+                                    &name_value.lit,
+                                    "separator must not contain `%` or hex digits",
+                                ));
+                            }
+                        }
+                        syn::NestedMeta::Meta(NameValue(syn::MetaNameValue {
+                            path,
+                            lit: Str(literal),
+                            ..
+                        })) if path.is_ident("crate") => krate = crate_path(literal)?,
+                        nested => {
+                            return Err(syn::Error::new_spanned(
+                                nested,
+                                "unknown resource id option, expected `separator = \"...\"` or \
+                                 `crate = \"...\"`",
+                            ))
+                        }
+                    }
+                }
+            }
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "expected `#[resource_id(separator = \"...\")]`",
+                ))
+            }
         }
     }
+    let fields = match &ast.data {
+        syn::Data::Struct(data_struct) if !data_struct.fields.is_empty() => &data_struct.fields,
+        _ => {
+            return Err(syn::Error::new(
+                name.span(),
+                "ResourceId must be derived from a struct with fields",
+            ))
+        }
+    };
+    let members: Vec<syn::Member> = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(syn::Index::from(index)),
+        })
+        .collect();
+    let parts = members.len();
+    // Parts followed by a separator are escaped, the last one takes the rest of the id
+    let (last, init) = members.split_last().expect("struct has fields");
+    Ok(quote! {
+        impl #impl_generics #krate::ResourceId for #name #ty_generics #where_clause {
+            fn format_id(&self) -> String {
+                let mut id = String::new();
+                #(
+                    id.push_str(&#krate::export::escape_id_part(
+                        &#krate::ResourceId::format_id(&self.#init),
+                        #separator,
+                    ));
+                    id.push_str(#separator);
+                )*
+                id.push_str(&#krate::ResourceId::format_id(&self.#last));
+                id
+            }
+
+            fn parse_id(id: &str) -> Result<Self, #krate::resource::ParseIdError> {
+                let mut parts = id.splitn(#parts, #separator);
+                Ok(#name {
+                    #(#init: parts
+                        .next()
+                        .and_then(#krate::export::unescape_id_part)
+                        .and_then(|part| #krate::ResourceId::parse_id(&part).ok())
+                        .ok_or_else(|| #krate::resource::ParseIdError::new(id))?,)*
+                    #last: parts
+                        .next()
+                        .and_then(|part| #krate::ResourceId::parse_id(part).ok())
+                        .ok_or_else(|| #krate::resource::ParseIdError::new(id))?,
+                })
+            }
+        }
+
+        impl #impl_generics std::fmt::Display for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str(&#krate::ResourceId::format_id(self))
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(ResourceId, attributes(resource_id))]
+pub fn resource_id_derive(input: TokenStream) -> TokenStream {
+    expand_proc_macro(input, impl_resource_id)
+}
+
+#[proc_macro_derive(InputResource, attributes(jsonapi))]
+pub fn input_resource_derive(input: TokenStream) -> TokenStream {
+    expand_proc_macro(input, impl_input_resource)
+}
+
+#[proc_macro_derive(JsonApiResource, attributes(jsonapi))]
+pub fn jsonapi_resource_derive(input: TokenStream) -> TokenStream {
+    expand_proc_macro(input, impl_jsonapi_resource)
+}
+
+// Thanks to diesel. Errors are reported as `compile_error!` at the span they point to
+fn expand_proc_macro<T: syn::parse::Parse>(
+    input: TokenStream,
+    f: fn(T) -> syn::Result<proc_macro2::TokenStream>,
+) -> TokenStream {
+    syn::parse(input)
+        .and_then(f)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
 }
@@ -10,6 +10,7 @@ fn test_generate_single_field() {
         code: None,
         detail: None,
         title: None,
+        source: None,
     };
     assert_eq!(generated_error, result_error);
 }
@@ -23,6 +24,7 @@ fn test_generate_multiple_fields() {
         code: None,
         detail: None,
         title: None,
+        source: None,
     };
     assert_eq!(generated_error, result_error);
 }
@@ -44,6 +46,7 @@ fn test_generate_all_fields() {
             "Failed completely and utterly, please god help me!",
         )),
         title: Some(String::from("Super failure")),
+        source: None,
     };
     assert_eq!(generated_error, result_error);
 }
@@ -1,5 +1,4 @@
 #![allow(dead_code)]
-use rocket_jsonapi::links::Link::Object;
 use rocket_jsonapi::links::{Link, LinkObject, Linkify};
 use rocket_jsonapi::relationship::{
     HaveRelationship, RelationObject, RelationObjectify, Relationships,
@@ -43,9 +42,9 @@ impl ResourceIdentifiable for Article {
 
 impl Linkify for Article {
     fn get_links(&self) -> Vec<Link> {
-        vec![Object(
+        vec![Link::object(
             "self",
-            LinkObject::new("".to_owned(), Box::new(ARTICLE_LINK_META)),
+            LinkObject::new("").meta(&ARTICLE_LINK_META),
         )]
     }
 }
@@ -62,13 +61,11 @@ impl<'a> HaveRelationship<'a, &'a ProofReader> for Article {
     }
 }
 
-/*
-*/
 impl Relationships for Article {
     fn get_all_relation_objects(&self) -> Vec<RelationObject> {
         vec![
-            <dyn RelationObjectify<Author>>::get_relation_object(self),
-            <dyn RelationObjectify<&ProofReader>>::get_relation_object(self),
+            <Self as RelationObjectify<Author>>::get_relation_object(self, "author"),
+            <Self as RelationObjectify<&ProofReader>>::get_relation_object(self, "proof_reader"),
         ]
     }
 }
@@ -123,13 +120,13 @@ impl Linkify for Author {
 
 struct ProofReader(Person);
 
-impl ResourceType for &ProofReader {
+impl ResourceType for ProofReader {
     fn get_type() -> &'static str {
         Person::get_type()
     }
 }
 
-impl ResourceIdentifiable for &ProofReader {
+impl ResourceIdentifiable for ProofReader {
     type IdType = i32;
 
     fn get_id(&self) -> &Self::IdType {
@@ -137,4 +134,4 @@ impl ResourceIdentifiable for &ProofReader {
     }
 }
 
-impl Linkify for &ProofReader {}
+impl Linkify for ProofReader {}
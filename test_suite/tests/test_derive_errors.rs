@@ -0,0 +1,9 @@
+// Verifies that invalid input to rocket_jsonapi_derive is reported with errors pointing at the
+// offending struct, field or attribute. Run with `TRYBUILD=overwrite` to update the expected
+// output in `tests/ui`.
+
+#[test]
+fn test_derive_errors() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -1,8 +1,13 @@
 #![allow(dead_code)]
 // These test simply verifies that rocket_jsonapi_derive is able to produce code that compiles.
 
+use rocket_jsonapi::links::LinkContext;
+use rocket_jsonapi::relationship::{HaveRelationship, RelationObject, Relationships};
+use rocket_jsonapi::resource::{InstanceType, ParseIdError, ResourceMeta};
+use rocket_jsonapi::response::JsonApiCollection;
 use rocket_jsonapi::Linkify;
-use rocket_jsonapi::{ResourceIdentifiable, ResourceType};
+use rocket_jsonapi::{ResourceId, ResourceIdentifiable, ResourceType};
+use serde_json::{json, Value};
 
 #[test]
 fn test_gen_linkify_simple() {
@@ -14,6 +19,170 @@ fn test_gen_linkify_simple() {
     assert_linkify::<Simple>();
 }
 
+fn links_json<T: Linkify>(resource: &T, context: Option<&LinkContext>) -> Value {
+    let links = match context {
+        Some(context) => resource.get_links_in(context),
+        None => resource.get_links(),
+    };
+    links
+        .into_iter()
+        .map(|link| {
+            (
+                link.key().to_string(),
+                serde_json::to_value(link_value(&link)).unwrap(),
+            )
+        })
+        .collect::<serde_json::Map<String, Value>>()
+        .into()
+}
+
+fn link_value(link: &rocket_jsonapi::links::Link) -> Value {
+    match link {
+        rocket_jsonapi::links::Link::Url(_, url) => json!(url),
+        rocket_jsonapi::links::Link::Object(_, object) => serde_json::to_value(object).unwrap(),
+    }
+}
+
+#[derive(serde::Serialize)]
+struct SchemaMeta {
+    version: u32,
+}
+
+const SCHEMA_META: SchemaMeta = SchemaMeta { version: 2 };
+
+#[test]
+fn test_gen_linkify_templates() {
+    #[derive(ResourceType, ResourceIdentifiable, Linkify)]
+    #[resource_ident_id = "key"]
+    #[links(self = "/articles/{id}", related = "/blogs/{blog}/articles/{ key }")]
+    #[links(
+        describedby(href = "https://example.com/schemas/article", title = "Schema", meta = SCHEMA_META),
+        "first-comment" = "/comments/{first_comment}"
+    )]
+    struct Article {
+        key: String,
+        blog: u32,
+        first_comment: i64,
+    }
+    let article = Article {
+        key: String::from("a1"),
+        blog: 7,
+        first_comment: 12,
+    };
+    let expected = json!({
+        "self": "/articles/a1",
+        "related": "/blogs/7/articles/a1",
+        "describedby": {
+            "href": "https://example.com/schemas/article",
+            "title": "Schema",
+            "meta": { "version": 2 }
+        },
+        "first-comment": "/comments/12"
+    });
+    assert_eq!(links_json(&article, None), expected);
+    // Paths from the server root get the base URL of the context, absolute URLs are kept
+    assert_eq!(
+        links_json(
+            &article,
+            Some(&LinkContext::new("https://example.com", "/api"))
+        ),
+        json!({
+            "self": "https://example.com/articles/a1",
+            "related": "https://example.com/blogs/7/articles/a1",
+            "describedby": expected["describedby"],
+            "first-comment": "https://example.com/comments/12"
+        })
+    );
+}
+
+#[test]
+fn test_gen_linkify_borrowed_meta() {
+    #[derive(ResourceType, ResourceIdentifiable, Linkify)]
+    #[links(comments(href = "/articles/{id}/comments", meta = self.comments_meta))]
+    struct Article {
+        id: u32,
+        comments_meta: SchemaMeta,
+    }
+    let article = Article {
+        id: 1,
+        comments_meta: SchemaMeta { version: 3 },
+    };
+    assert_eq!(
+        links_json(&article, None),
+        json!({
+            "comments": {
+                "href": "/articles/1/comments",
+                "meta": { "version": 3 }
+            }
+        })
+    );
+}
+
+#[test]
+fn test_gen_linkify_self_link() {
+    #[derive(ResourceType, ResourceIdentifiable, Linkify)]
+    #[links(
+        self(href = "/people/{id}", title = "Person"),
+        related = "/teams/{team}"
+    )]
+    struct Person {
+        id: u64,
+        team: u32,
+    }
+    #[derive(Linkify)]
+    #[links(related = "/teams/{team}")]
+    struct Member {
+        team: u32,
+    }
+    let context = LinkContext::new("https://example.com", "/api");
+    // Only the `self` link is built, for the links of relationships
+    assert_eq!(
+        Person { id: 3, team: 7 }.self_link_in(&context),
+        Some(String::from("https://example.com/people/3"))
+    );
+    assert_eq!(Member { team: 7 }.self_link_in(&context), None);
+}
+
+#[test]
+fn test_gen_linkify_encoded_placeholders() {
+    #[derive(ResourceType, ResourceIdentifiable, Linkify)]
+    #[links(self = "/files/{id}", related = "/folders/{folder}/files")]
+    struct File {
+        id: String,
+        folder: String,
+    }
+    let file = File {
+        id: String::from("report 2021.pdf"),
+        folder: String::from("a/b?c"),
+    };
+    assert_eq!(
+        links_json(&file, None),
+        json!({
+            "self": "/files/report%202021.pdf",
+            "related": "/folders/a%2Fb%3Fc/files"
+        })
+    );
+}
+
+#[test]
+fn test_gen_linkify_escaped_braces() {
+    #[derive(ResourceType, ResourceIdentifiable, Linkify)]
+    #[links(
+        self = "/articles/{id}",
+        describedby = "https://example.com/templates/{{id}}/{id}{{}}"
+    )]
+    struct Article {
+        id: u32,
+    }
+    assert_eq!(
+        links_json(&Article { id: 4 }, None),
+        json!({
+            "self": "/articles/4",
+            "describedby": "https://example.com/templates/{id}/4{}"
+        })
+    );
+}
+
 #[test]
 fn test_gen_resource_type_simple() {
     #[derive(ResourceType)]
@@ -58,6 +227,34 @@ fn test_gen_resource_identifiable_custom_type() {
     assert_eq!("1", simple_resource.get_id());
 }
 
+#[test]
+fn test_gen_resource_type_arbitrary_string() {
+    #[derive(ResourceType)]
+    #[resource_ident_type = "2fa-tokens"]
+    struct TwoFactorToken;
+    assert_eq!("2fa-tokens", TwoFactorToken::get_type());
+}
+
+#[test]
+fn test_gen_resource_type_rename_all() {
+    #[derive(ResourceType)]
+    #[resource_ident_type(rename_all = "kebab-case", plural)]
+    struct BlogPost;
+    #[derive(ResourceType)]
+    #[resource_ident_type(rename_all = "snake_case")]
+    struct HTTPRequestLog;
+    #[derive(ResourceType)]
+    #[resource_ident_type(rename_all = "camelCase", plural)]
+    struct ProductCategory;
+    #[derive(ResourceType)]
+    #[resource_ident_type(plural)]
+    struct Address;
+    assert_eq!("blog-posts", BlogPost::get_type());
+    assert_eq!("http_request_log", HTTPRequestLog::get_type());
+    assert_eq!("productCategories", ProductCategory::get_type());
+    assert_eq!("Addresses", Address::get_type());
+}
+
 #[test]
 fn test_gen_resource_identifiable_id_i32() {
     #[derive(ResourceType, ResourceIdentifiable)]
@@ -114,6 +311,442 @@ fn test_gen_resource_identifiable_id_custom() {
     assert_eq!(test_id, *resource.get_id());
 }
 
+#[derive(serde::Serialize, ResourceType, ResourceIdentifiable, Linkify)]
+struct Person {
+    id: i32,
+}
+
+fn relationships_json(relationships: Vec<RelationObject>) -> Value {
+    relationships
+        .into_iter()
+        .map(|relationship| {
+            (
+                relationship.name().to_string(),
+                serde_json::to_value(relationship).unwrap(),
+            )
+        })
+        .collect::<serde_json::Map<String, Value>>()
+        .into()
+}
+
+#[test]
+fn test_gen_relationships_fields() {
+    #[derive(rocket_jsonapi::Relationships)]
+    struct Article {
+        id: i32,
+        #[jsonapi(relationship)]
+        author: Person,
+        #[jsonapi(relationship = "proof-reader")]
+        proof_reader: Option<Person>,
+        #[jsonapi(relationship)]
+        comments: Vec<Person>,
+    }
+    let article = Article {
+        id: 1,
+        author: Person { id: 2 },
+        proof_reader: None,
+        comments: vec![Person { id: 3 }, Person { id: 4 }],
+    };
+    // The relations are borrowed from the resource
+    let author: &Person = article.get_relation();
+    assert_eq!(author.id, 2);
+    assert_eq!(
+        relationships_json(article.get_all_relation_objects()),
+        json!({
+            "author": { "data": { "id": "2", "type": "Person" } },
+            "proof-reader": { "data": null },
+            "comments": {
+                "data": [{ "id": "3", "type": "Person" }, { "id": "4", "type": "Person" }]
+            }
+        })
+    );
+}
+
+#[test]
+fn test_gen_relationships_attributes() {
+    #[derive(rocket_jsonapi::Relationships)]
+    #[relationship(name = "author", to = Person)]
+    #[relationship(name = "comments", to = JsonApiCollection<Person>)]
+    #[relationship(name = "editor", to = Option<Person>)]
+    struct Article {
+        author_id: i32,
+        editor_id: Option<i32>,
+    }
+    impl HaveRelationship<'_, Person> for Article {
+        fn get_relation(&self) -> Person {
+            Person { id: self.author_id }
+        }
+    }
+    impl HaveRelationship<'_, JsonApiCollection<Person>> for Article {
+        fn get_relation(&self) -> JsonApiCollection<Person> {
+            JsonApiCollection::data(vec![])
+        }
+    }
+    impl HaveRelationship<'_, Option<Person>> for Article {
+        fn get_relation(&self) -> Option<Person> {
+            self.editor_id.map(|id| Person { id })
+        }
+    }
+    let article = Article {
+        author_id: 2,
+        editor_id: Some(5),
+    };
+    assert_eq!(
+        relationships_json(article.get_all_relation_objects()),
+        json!({
+            "author": { "data": { "id": "2", "type": "Person" } },
+            "comments": { "data": [] },
+            "editor": { "data": { "id": "5", "type": "Person" } }
+        })
+    );
+}
+
+#[test]
+fn test_gen_relationships_borrowed() {
+    #[derive(rocket_jsonapi::Relationships)]
+    #[relationship(name = "editor", to = Option<&Person>)]
+    #[relationship(name = "reviewers", to = JsonApiCollection<&Person>)]
+    struct Article {
+        editor: Option<Person>,
+        reviewers: Vec<Person>,
+    }
+    impl<'a> HaveRelationship<'a, Option<&'a Person>> for Article {
+        fn get_relation(&'a self) -> Option<&'a Person> {
+            self.editor.as_ref()
+        }
+    }
+    impl<'a> HaveRelationship<'a, JsonApiCollection<&'a Person>> for Article {
+        fn get_relation(&'a self) -> JsonApiCollection<&'a Person> {
+            JsonApiCollection::data(self.reviewers.iter().collect())
+        }
+    }
+    let article = Article {
+        editor: Some(Person { id: 5 }),
+        reviewers: vec![Person { id: 6 }],
+    };
+    assert_eq!(
+        relationships_json(article.get_all_relation_objects()),
+        json!({
+            "editor": { "data": { "id": "5", "type": "Person" } },
+            "reviewers": { "data": [{ "id": "6", "type": "Person" }] }
+        })
+    );
+}
+
+#[test]
+fn test_gen_generics() {
+    #[derive(ResourceType, ResourceIdentifiable, Linkify)]
+    #[links(self = "/pages/{id}")]
+    struct Page<'a> {
+        id: &'a str,
+        title: &'a str,
+    }
+    #[derive(ResourceType, ResourceIdentifiable, Linkify, rocket_jsonapi::Relationships)]
+    #[resource_ident_type = "envelopes"]
+    #[links(self = "/envelopes/{id}")]
+    struct Envelope<'a, T>
+    where
+        T: ResourceIdentifiable + Linkify,
+    {
+        id: i32,
+        #[jsonapi(relationship)]
+        content: T,
+        #[jsonapi(relationship)]
+        pages: Vec<Page<'a>>,
+    }
+    assert_linkify::<Envelope<Person>>();
+    assert_resource_identifiable::<Envelope<Page>>();
+    let page = Page {
+        id: "intro",
+        title: "Introduction",
+    };
+    assert_eq!("Page", Page::get_type());
+    assert_eq!("intro", *page.get_id());
+    assert_eq!("Introduction", page.title);
+    let envelope = Envelope {
+        id: 1,
+        content: Person { id: 2 },
+        pages: vec![page],
+    };
+    assert_eq!("envelopes", Envelope::<Person>::get_type());
+    assert_eq!(
+        links_json(&envelope, None),
+        json!({ "self": "/envelopes/1" })
+    );
+    let content: &Person = envelope.get_relation();
+    assert_eq!(content.id, 2);
+    assert_eq!(
+        relationships_json(envelope.get_all_relation_objects()),
+        json!({
+            "content": { "data": { "id": "2", "type": "Person" } },
+            "pages": { "data": [{ "id": "intro", "type": "Page" }] }
+        })
+    );
+}
+
+#[test]
+fn test_gen_jsonapi_resource() {
+    #[derive(rocket_jsonapi::JsonApiResource)]
+    #[jsonapi(rename_all = "kebab-case", plural, id = "slug")]
+    #[jsonapi(links(self = "/blog-posts/{slug}"))]
+    #[jsonapi(relationship(name = "editor", to = Option<Person>))]
+    #[jsonapi(meta(words = self.text.split_whitespace().count(), "read-only" = true))]
+    struct BlogPost {
+        slug: String,
+        text: String,
+        #[jsonapi(relationship)]
+        author: Person,
+        editor_id: Option<i32>,
+    }
+    impl HaveRelationship<'_, Option<Person>> for BlogPost {
+        fn get_relation(&self) -> Option<Person> {
+            self.editor_id.map(|id| Person { id })
+        }
+    }
+    let post = BlogPost {
+        slug: "hello-world".to_string(),
+        text: "Hello JSON:API world".to_string(),
+        author: Person { id: 2 },
+        editor_id: None,
+    };
+    assert_eq!("blog-posts", BlogPost::get_type());
+    assert_eq!("hello-world", post.get_id());
+    assert_eq!(
+        links_json(&post, None),
+        json!({ "self": "/blog-posts/hello-world" })
+    );
+    assert_eq!(
+        relationships_json(post.get_all_relation_objects()),
+        json!({
+            "editor": { "data": null },
+            "author": { "data": { "id": "2", "type": "Person" } }
+        })
+    );
+    assert_eq!(
+        serde_json::to_value(post.get_meta()).unwrap(),
+        json!({ "words": 3, "read-only": true })
+    );
+}
+
+mod reexport {
+    pub use rocket_jsonapi as jsonapi;
+}
+
+#[test]
+fn test_gen_jsonapi_resource_crate_override() {
+    #[derive(rocket_jsonapi::JsonApiResource)]
+    #[jsonapi(crate = "reexport::jsonapi", type = "2fa-tokens")]
+    #[jsonapi(links(self = "/tokens/{id}"))]
+    struct Token {
+        id: i32,
+        #[jsonapi(relationship)]
+        owner: Person,
+    }
+    assert_linkify::<Token>();
+    assert_resource_identifiable::<Token>();
+    assert_eq!("2fa-tokens", Token::get_type());
+}
+
+#[test]
+fn test_gen_input_resource_crate_override() {
+    #[derive(serde::Deserialize, ResourceType)]
+    #[resource_ident_type = "images"]
+    struct NewImage {
+        url: String,
+    }
+    #[derive(rocket_jsonapi::InputResource)]
+    #[jsonapi(crate = "reexport::jsonapi")]
+    enum NewMedia {
+        Image(NewImage),
+    }
+    assert_eq!(
+        <NewMedia as rocket_jsonapi::InputResource>::input_types(),
+        vec!["images"]
+    );
+}
+
+#[test]
+fn test_gen_resource_id_crate_override() {
+    #[derive(Debug, PartialEq, rocket_jsonapi::ResourceId)]
+    #[resource_id(crate = "reexport::jsonapi", separator = ":")]
+    struct VersionKey(u32, u32);
+    assert_eq!(VersionKey::parse_id("1:2"), Ok(VersionKey(1, 2)));
+}
+
+#[test]
+fn test_gen_jsonapi_resource_transparent() {
+    #[derive(serde::Serialize, rocket_jsonapi::JsonApiResource)]
+    #[jsonapi(type = "people", links(self = "/people/{id}"))]
+    struct User {
+        id: i32,
+        name: String,
+        #[jsonapi(relationship)]
+        friend: Option<Person>,
+    }
+    // Forwards everything to the user
+    #[derive(rocket_jsonapi::JsonApiResource)]
+    #[jsonapi(transparent)]
+    struct Member(User);
+    // Overrides the type and links of the user, and has no relationships
+    #[derive(rocket_jsonapi::JsonApiResource)]
+    #[jsonapi(transparent, type = "authors", links(self = "/authors/{id}"))]
+    #[jsonapi(relationship(name = "posts", to = JsonApiCollection<Person>))]
+    struct Author {
+        user: User,
+    }
+    impl HaveRelationship<'_, JsonApiCollection<Person>> for Author {
+        fn get_relation(&self) -> JsonApiCollection<Person> {
+            JsonApiCollection::data(vec![])
+        }
+    }
+    let user = || User {
+        id: 1,
+        name: "Ann".to_string(),
+        friend: Some(Person { id: 2 }),
+    };
+    let member = Member(user());
+    assert_eq!("people", Member::get_type());
+    assert_eq!(1, *member.get_id());
+    assert_eq!(links_json(&member, None), json!({ "self": "/people/1" }));
+    assert_eq!(
+        relationships_json(member.get_all_relation_objects()),
+        json!({ "friend": { "data": { "id": "2", "type": "Person" } } })
+    );
+    assert_eq!(
+        serde_json::to_value(&member).unwrap(),
+        json!({ "id": 1, "name": "Ann", "friend": { "id": 2 } })
+    );
+    let author = Author { user: user() };
+    assert_eq!("authors", Author::get_type());
+    assert_eq!(1, *author.get_id());
+    assert_eq!(links_json(&author, None), json!({ "self": "/authors/1" }));
+    assert_eq!(
+        relationships_json(author.get_all_relation_objects()),
+        json!({ "posts": { "data": [] } })
+    );
+    assert_eq!(
+        serde_json::to_value(&author).unwrap(),
+        serde_json::to_value(&author.user).unwrap()
+    );
+}
+
+#[test]
+fn test_gen_jsonapi_resource_transparent_without_relationships() {
+    #[derive(rocket_jsonapi::JsonApiResource)]
+    #[jsonapi(transparent, rename_all = "kebab-case", plural)]
+    struct ProofReader(Person);
+    assert_eq!("proof-readers", ProofReader::get_type());
+    assert!(ProofReader(Person { id: 3 })
+        .get_all_relation_objects()
+        .is_empty());
+}
+
+#[test]
+fn test_gen_jsonapi_resource_type_field() {
+    #[derive(rocket_jsonapi::JsonApiResource)]
+    #[jsonapi(type = "vehicles", type_field = "kind")]
+    struct Vehicle {
+        id: i32,
+        kind: String,
+    }
+    #[derive(rocket_jsonapi::JsonApiResource)]
+    #[jsonapi(type = "owners")]
+    struct Owner {
+        id: i32,
+        #[jsonapi(relationship)]
+        vehicles: Vec<Vehicle>,
+    }
+    let car = Vehicle {
+        id: 1,
+        kind: "cars".to_string(),
+    };
+    assert_eq!("vehicles", Vehicle::get_type());
+    assert_eq!("cars", car.instance_type());
+    let owner = Owner {
+        id: 2,
+        vehicles: vec![
+            car,
+            Vehicle {
+                id: 3,
+                kind: "bikes".to_string(),
+            },
+        ],
+    };
+    assert_eq!(2, owner.id);
+    assert_eq!(
+        relationships_json(owner.get_all_relation_objects()),
+        json!({
+            "vehicles": {
+                "data": [{ "id": "1", "type": "cars" }, { "id": "3", "type": "bikes" }]
+            }
+        })
+    );
+}
+
+#[test]
+fn test_gen_resource_id_composite() {
+    #[derive(Debug, PartialEq, rocket_jsonapi::ResourceId)]
+    struct PostKey {
+        author_id: i32,
+        slug: String,
+    }
+    #[derive(Debug, PartialEq, rocket_jsonapi::ResourceId)]
+    #[resource_id(separator = ":")]
+    struct VersionKey(u32, u32, String);
+    #[derive(ResourceType, ResourceIdentifiable)]
+    struct Post {
+        id: PostKey,
+    }
+    let key = PostKey {
+        author_id: 3,
+        slug: "hello-world".to_string(),
+    };
+    assert_eq!(key.format_id(), "3-hello-world");
+    assert_eq!(PostKey::parse_id("3-hello-world"), Ok(key));
+    assert_eq!(
+        PostKey::parse_id("hello-world"),
+        Err(ParseIdError::new("hello-world"))
+    );
+    assert_eq!(PostKey::parse_id("3"), Err(ParseIdError::new("3")));
+    let post = Post {
+        id: PostKey::parse_id("7-intro").unwrap(),
+    };
+    assert_eq!(post.get_id().to_string(), "7-intro");
+    let version = VersionKey(1, 2, "beta:3".to_string());
+    assert_eq!(version.format_id(), "1:2:beta:3");
+    assert_eq!(VersionKey::parse_id("1:2:beta:3"), Ok(version));
+}
+
+#[test]
+fn test_gen_resource_id_escaped_parts() {
+    #[derive(Debug, PartialEq, rocket_jsonapi::ResourceId)]
+    struct OffsetKey(i32, i64);
+    #[derive(Debug, PartialEq, rocket_jsonapi::ResourceId)]
+    struct DocumentKey {
+        tenant: uuid::Uuid,
+        version: uuid::Uuid,
+    }
+    #[derive(Debug, PartialEq, rocket_jsonapi::ResourceId)]
+    #[resource_id(separator = "::")]
+    struct PathKey(String, String, u8);
+    // The separator and `%` are escaped in every part but the last
+    let offset = OffsetKey(-3, -4);
+    assert_eq!(offset.format_id(), "%2D3--4");
+    assert_eq!(OffsetKey::parse_id(&offset.format_id()), Ok(offset));
+    let tenant = uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+    let version = uuid::Uuid::parse_str("0e5fe0c8-426f-10b1-9247-bb68067e5504").unwrap();
+    let document = DocumentKey { tenant, version };
+    assert_eq!(
+        document.format_id(),
+        "67e55044%2D10b1%2D426f%2D9247%2Dbb680e5fe0c8-0e5fe0c8-426f-10b1-9247-bb68067e5504"
+    );
+    assert_eq!(DocumentKey::parse_id(&document.format_id()), Ok(document));
+    let path = PathKey("a:b::c".to_string(), "100%".to_string(), 7);
+    assert_eq!(path.format_id(), "a%3Ab%3A%3Ac::100%25::7");
+    assert_eq!(PathKey::parse_id(&path.format_id()), Ok(path));
+    assert_eq!(OffsetKey::parse_id("%2-4"), Err(ParseIdError::new("%2-4")));
+}
+
 fn assert_linkify<T: Linkify>() {}
 fn assert_resource_type<T: ResourceType>() {}
 fn assert_resource_identifiable<T: ResourceIdentifiable>() {}
@@ -0,0 +1,389 @@
+#![allow(dead_code)]
+#![feature(proc_macro_hygiene, decl_macro)]
+
+#[macro_use]
+extern crate rocket;
+
+use rocket_jsonapi::relationship::HaveRelationship;
+use rocket_jsonapi::{InputResource, Linkify, Relationships, ResourceIdentifiable, ResourceType};
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Serialize, Deserialize, ResourceType, ResourceIdentifiable, Linkify)]
+struct Test {
+    id: i32,
+    message: String,
+}
+
+#[derive(Deserialize, ResourceType)]
+#[resource_ident_type = "Test"]
+#[serde(deny_unknown_fields)]
+struct TestPatch {
+    message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ResourceType, ResourceIdentifiable, Linkify, Relationships)]
+#[resource_ident_type = "comments"]
+#[links(self = "/comments/{id}")]
+#[relationship(name = "author", to = Option<Test>)]
+struct Comment {
+    id: i32,
+    text: String,
+}
+
+impl HaveRelationship<'_, Option<Test>> for Comment {
+    fn get_relation(&self) -> Option<Test> {
+        None
+    }
+}
+
+#[derive(InputResource)]
+enum Input {
+    Test(Test),
+    Comment(Comment),
+}
+
+mod test_atomic_operations {
+    use crate::{Input, Test, TestPatch};
+    use rocket::http::{Header, Status};
+    use rocket::local::Client;
+    use rocket::Rocket;
+    use rocket_jsonapi::atomic::{
+        operation_error, AtomicResult, JsonApiAtomicRequest, JsonApiAtomicResponse, LocalIds,
+        Operation, OperationTarget, ATOMIC_EXTENSION,
+    };
+    use rocket_jsonapi::error::{JsonApiError, JsonApiResponseError};
+    use rocket_jsonapi::json_api_error;
+    use rocket_jsonapi::request::MediaTypeSupport;
+    use serde_json::{json, Value};
+
+    #[post("/operations", data = "<operations>")]
+    fn operations(
+        operations: Result<JsonApiAtomicRequest<Test, TestPatch>, JsonApiResponseError>,
+    ) -> JsonApiAtomicResponse {
+        let operations = match operations {
+            Ok(operations) => operations,
+            Err(error) => return JsonApiAtomicResponse(Err(error)),
+        };
+        let mut local_ids = LocalIds::new();
+        // Only resources created by this request exist
+        let mut created = vec![];
+        let mut results = vec![];
+        for (index, operation) in operations.0.into_iter().enumerate() {
+            match operation {
+                Operation::Add { lid, data } => {
+                    let id = index as i32 + 1;
+                    if let Some(lid) = lid {
+                        local_ids.insert(lid, id.to_string());
+                    }
+                    created.push(id.to_string());
+                    results.push(AtomicResult::data(Test { id, ..data }));
+                }
+                Operation::Update {
+                    target: OperationTarget::Ref(target),
+                    ..
+                } => match local_ids.resolve(&target) {
+                    Some(id) if created.iter().any(|created| created == id) => {
+                        results.push(AtomicResult::empty())
+                    }
+                    _ => {
+                        return JsonApiAtomicResponse(Err(JsonApiResponseError::new(
+                            Status::NotFound,
+                            vec![operation_error(
+                                index,
+                                json_api_error!(title = String::from("Not found")),
+                            )],
+                        )))
+                    }
+                },
+                _ => results.push(AtomicResult::empty()),
+            }
+        }
+        JsonApiAtomicResponse(Ok(results))
+    }
+
+    #[post("/mixed", data = "<operations>")]
+    fn mixed(
+        operations: Result<JsonApiAtomicRequest<Input>, JsonApiResponseError>,
+    ) -> JsonApiAtomicResponse {
+        let operations = match operations {
+            Ok(operations) => operations,
+            Err(error) => return JsonApiAtomicResponse(Err(error)),
+        };
+        let results = operations
+            .0
+            .into_iter()
+            .map(|operation| match operation {
+                Operation::Add {
+                    data: Input::Test(test),
+                    ..
+                } => AtomicResult::data(test),
+                Operation::Add {
+                    data: Input::Comment(comment),
+                    ..
+                } => AtomicResult::data(comment),
+                _ => AtomicResult::empty(),
+            })
+            .collect();
+        JsonApiAtomicResponse(Ok(results))
+    }
+
+    fn rocket() -> Rocket {
+        rocket::ignite()
+            .manage(MediaTypeSupport::new().extension(ATOMIC_EXTENSION))
+            .mount("/", routes![operations, mixed])
+    }
+
+    fn atomic_media_type() -> String {
+        format!("application/vnd.api+json; ext=\"{}\"", ATOMIC_EXTENSION)
+    }
+
+    fn dispatch(body: Value) -> (Status, Option<Value>) {
+        dispatch_to("/operations", body)
+    }
+
+    fn dispatch_to(uri: &str, body: Value) -> (Status, Option<Value>) {
+        dispatch_with(uri, body, vec![])
+    }
+
+    fn dispatch_with(
+        uri: &str,
+        body: Value,
+        headers: Vec<Header<'static>>,
+    ) -> (Status, Option<Value>) {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let mut request = client.post(uri.to_string());
+        request.add_header(Header::new("Content-Type", atomic_media_type()));
+        request.add_header(Header::new("Accept", atomic_media_type()));
+        for header in headers {
+            request.add_header(header);
+        }
+        request.set_body(body.to_string());
+        let mut response = request.dispatch();
+        let body = response
+            .body_string()
+            .map(|body| serde_json::from_str(&body).unwrap());
+        (response.status(), body)
+    }
+
+    #[test]
+    fn test_atomic_add_results() {
+        let (status, body) = dispatch(json!({
+            "atomic:operations": [{
+                "op": "add",
+                "data": {
+                    "type": "Test",
+                    "lid": "a",
+                    "attributes": { "id": 0, "message": "Hello" }
+                }
+            }, {
+                "op": "update",
+                "data": {
+                    "type": "Test",
+                    "lid": "a",
+                    "attributes": { "message": "Hallo" }
+                }
+            }]
+        }));
+        assert_eq!(status, Status::Ok);
+        assert_eq!(
+            body.unwrap(),
+            json!({
+                "atomic:results": [{
+                    "data": {
+                        "id": "1",
+                        "type": "Test",
+                        "attributes": { "id": 1, "message": "Hello" }
+                    }
+                }, {}]
+            })
+        );
+    }
+
+    #[test]
+    fn test_atomic_mixed_types() {
+        let (status, body) = dispatch_to(
+            "/mixed",
+            json!({
+                "atomic:operations": [{
+                    "op": "add",
+                    "data": {
+                        "type": "Test",
+                        "attributes": { "id": 1, "message": "Hello" }
+                    }
+                }, {
+                    "op": "add",
+                    "data": {
+                        "type": "comments",
+                        "attributes": { "id": 2, "text": "First" }
+                    }
+                }, {
+                    "op": "update",
+                    "data": {
+                        "type": "comments",
+                        "id": "2",
+                        "attributes": { "id": 2, "text": "Second" }
+                    }
+                }]
+            }),
+        );
+        assert_eq!(status, Status::Ok);
+        let body = body.unwrap();
+        assert_eq!(body["atomic:results"][0]["data"]["type"], "Test");
+        assert_eq!(body["atomic:results"][1]["data"]["type"], "comments");
+        assert_eq!(body["atomic:results"][2], json!({}));
+    }
+
+    #[test]
+    fn test_atomic_result_links() {
+        // Relationships of results are linked like those of primary data, from the host of the
+        // request
+        let (status, body) = dispatch_with(
+            "/mixed",
+            json!({
+                "atomic:operations": [{
+                    "op": "add",
+                    "data": {
+                        "type": "comments",
+                        "attributes": { "id": 2, "text": "First" }
+                    }
+                }]
+            }),
+            vec![Header::new("Host", "example.com")],
+        );
+        assert_eq!(status, Status::Ok);
+        assert_eq!(
+            body.unwrap()["atomic:results"][0]["data"]["relationships"]["author"]["links"],
+            json!({
+                "self": "http://example.com/comments/2/relationships/author",
+                "related": "http://example.com/comments/2/author"
+            })
+        );
+    }
+
+    #[test]
+    fn test_atomic_mixed_types_unaccepted() {
+        let (status, body) = dispatch_to(
+            "/mixed",
+            json!({
+                "atomic:operations": [{
+                    "op": "remove",
+                    "ref": { "type": "people", "id": "1" }
+                }]
+            }),
+        );
+        assert_eq!(status, Status::Conflict);
+        let body = body.unwrap();
+        assert_eq!(
+            body["errors"][0]["source"]["pointer"],
+            "/atomic:operations/0/ref/type"
+        );
+        assert_eq!(
+            body["errors"][0]["detail"],
+            "invalid value: string \"people\", expected one of Test, comments"
+        );
+    }
+
+    #[test]
+    fn test_atomic_invalid_update_attributes() {
+        let (status, body) = dispatch(json!({
+            "atomic:operations": [{
+                "op": "update",
+                "data": {
+                    "type": "Test",
+                    "id": "1",
+                    "attributes": { "text": "Hallo" }
+                }
+            }]
+        }));
+        assert_eq!(status, Status::Conflict);
+        assert_eq!(
+            body.unwrap()["errors"][0]["source"]["pointer"],
+            "/atomic:operations/0/data/attributes"
+        );
+    }
+
+    #[test]
+    fn test_atomic_no_results() {
+        let (status, body) = dispatch(json!({
+            "atomic:operations": [{
+                "op": "remove",
+                "ref": { "type": "Test", "id": "1" }
+            }]
+        }));
+        assert_eq!(status, Status::NoContent);
+        assert_eq!(body, None);
+    }
+
+    #[test]
+    fn test_atomic_invalid_operation() {
+        let (status, body) = dispatch(json!({
+            "atomic:operations": [{
+                "op": "remove",
+                "ref": { "type": "Test", "id": "1" }
+            }, {
+                "op": "remove",
+                "ref": { "type": "NotTest", "id": "1" }
+            }]
+        }));
+        assert_eq!(status, Status::Conflict);
+        let body = body.unwrap();
+        assert_eq!(
+            body["errors"][0]["source"]["pointer"],
+            "/atomic:operations/1/ref/type"
+        );
+        assert_eq!(body["errors"][0]["status"], "409");
+    }
+
+    #[test]
+    fn test_atomic_operation_error() {
+        let (status, body) = dispatch(json!({
+            "atomic:operations": [{
+                "op": "update",
+                "data": {
+                    "type": "Test",
+                    "id": "12",
+                    "attributes": { "message": "Hallo" }
+                }
+            }]
+        }));
+        assert_eq!(status, Status::NotFound);
+        assert_eq!(
+            body.unwrap(),
+            json!({
+                "errors": [{
+                    "title": "Not found",
+                    "source": { "pointer": "/atomic:operations/0" }
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn test_atomic_missing_extension() {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let mut request = client.post("/operations");
+        request.add_header(Header::new("Content-Type", "application/vnd.api+json"));
+        request.add_header(Header::new("Accept", "application/vnd.api+json"));
+        request.set_body(json!({ "atomic:operations": [] }).to_string());
+        let response = request.dispatch();
+        assert_eq!(response.status(), Status::UnsupportedMediaType);
+    }
+
+    #[test]
+    fn test_operation_error_pointer() {
+        let error = operation_error(
+            3,
+            JsonApiError {
+                source: Some(rocket_jsonapi::error::JsonApiErrorSource::pointer(
+                    "/data/attributes/message",
+                )),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            error.source.unwrap().pointer.unwrap(),
+            "/atomic:operations/3/data/attributes/message"
+        );
+    }
+}
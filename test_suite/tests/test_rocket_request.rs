@@ -445,3 +445,375 @@ mod test_request_input {
 
     // TODO return conflict if client ID already exists
 }
+
+mod test_request_resource_id {
+    use crate::Test;
+    use rocket::http::{Header, Status};
+    use rocket::local::Client;
+    use rocket_jsonapi::error::JsonApiResponseError;
+    use rocket_jsonapi::request::{JsonApiUpdateRequest, ResourceIdParam};
+    use rocket_jsonapi::response::JsonApiDataResponse;
+    use rocket_jsonapi::ResourceId;
+    use serde_json::json;
+
+    #[derive(ResourceId)]
+    struct PostKey {
+        author_id: i32,
+        slug: String,
+    }
+
+    #[patch("/tests", data = "<input>")]
+    fn typed_update(
+        input: Result<JsonApiUpdateRequest<Test, i32>, JsonApiResponseError>,
+    ) -> JsonApiDataResponse<Test> {
+        JsonApiDataResponse(input.map(|input| Test {
+            id: input.id,
+            message: String::from("Updated"),
+        }))
+    }
+
+    #[get("/posts/<key>")]
+    fn post(key: ResourceIdParam<PostKey>) -> String {
+        format!("{} {}", key.0.author_id, key.0.slug)
+    }
+
+    fn update(body: &str) -> (Status, Option<String>) {
+        let rocket = rocket::ignite().mount("/", routes![typed_update]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let mut request = client.patch("/tests");
+        request.add_header(Header::new("Content-Type", "application/vnd.api+json"));
+        request.add_header(Header::new("Accept", "application/vnd.api+json"));
+        let mut response = request.body(body).dispatch();
+        (response.status(), response.body_string())
+    }
+
+    #[test]
+    fn test_update_request_typed_id() {
+        let (status, body) = update(
+            r#"{ "data": { "type": "Test", "id": "12", "attributes": { "message": "Hay!" } } }"#,
+        );
+        assert_eq!(status, Status::Ok);
+        let body: serde_json::Value = serde_json::from_str(&body.unwrap()).unwrap();
+        assert_eq!(body["data"]["attributes"]["id"], 12);
+    }
+
+    #[test]
+    fn test_update_request_invalid_id() {
+        let (status, body) = update(
+            r#"{ "data": { "type": "Test", "id": "twelve", "attributes": { "message": "Hay!" } } }"#,
+        );
+        assert_eq!(status, Status::Conflict);
+        let body: serde_json::Value = serde_json::from_str(&body.unwrap()).unwrap();
+        assert_eq!(
+            body["errors"][0],
+            json!({
+                "status": "409",
+                "detail": "invalid resource id `twelve`",
+                "source": { "pointer": "/data/id" }
+            })
+        );
+    }
+
+    #[test]
+    fn test_update_request_invalid_type() {
+        let (status, body) = update(
+            r#"{ "data": { "type": "NotTest", "id": "12", "attributes": { "message": "Hay!" } } }"#,
+        );
+        assert_eq!(status, Status::Conflict);
+        let body: serde_json::Value = serde_json::from_str(&body.unwrap()).unwrap();
+        assert_eq!(
+            body["errors"][0]["source"],
+            json!({ "pointer": "/data/type" })
+        );
+    }
+
+    #[test]
+    fn test_resource_id_param() {
+        let rocket = rocket::ignite().mount("/", routes![post]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let mut response = client.get("/posts/3-hello%20world").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.body_string().unwrap(), "3 hello world");
+        let response = client.get("/posts/hello-world").dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+}
+
+mod test_request_polymorphic_input {
+    use crate::Test;
+    use rocket::http::{Header, Status};
+    use rocket::local::Client;
+    use rocket_jsonapi::error::JsonApiResponseError;
+    use rocket_jsonapi::request::JsonApiCreateRequest;
+    use rocket_jsonapi::response::JsonApiDataResponse;
+    use rocket_jsonapi::{InputResource, ResourceType};
+    use serde::Deserialize;
+
+    #[derive(Deserialize, ResourceType)]
+    #[resource_ident_type = "images"]
+    struct Image {
+        url: String,
+    }
+
+    #[derive(Deserialize, ResourceType)]
+    #[resource_ident_type = "videos"]
+    struct Video {
+        url: String,
+        duration: u32,
+    }
+
+    #[derive(InputResource)]
+    enum Media {
+        Image(Image),
+        Video(Video),
+    }
+
+    #[post("/media", data = "<input>")]
+    fn media(
+        input: Result<JsonApiCreateRequest<Media>, JsonApiResponseError>,
+    ) -> JsonApiDataResponse<Test> {
+        JsonApiDataResponse(input.map(|input| {
+            let message = match input.0 {
+                Media::Image(image) => format!("image {}", image.url),
+                Media::Video(video) => format!("video {} {}s", video.url, video.duration),
+            };
+            Test { id: 1, message }
+        }))
+    }
+
+    fn create(body: &str) -> (Status, serde_json::Value) {
+        let rocket = rocket::ignite().mount("/", routes![media]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let mut request = client.post("/media");
+        request.add_header(Header::new("Content-Type", "application/vnd.api+json"));
+        request.add_header(Header::new("Accept", "application/vnd.api+json"));
+        let mut response = request.body(body).dispatch();
+        let body = serde_json::from_str(&response.body_string().unwrap()).unwrap();
+        (response.status(), body)
+    }
+
+    #[test]
+    fn test_create_request_polymorphic() {
+        let (status, body) =
+            create(r#"{ "data": { "type": "images", "attributes": { "url": "cat.png" } } }"#);
+        assert_eq!(status, Status::Ok);
+        assert_eq!(body["data"]["attributes"]["message"], "image cat.png");
+        // Attributes before the type are deserialized once the type is known
+        let (status, body) = create(
+            r#"{ "data": { "attributes": { "url": "cat.mp4", "duration": 3 }, "type": "videos" } }"#,
+        );
+        assert_eq!(status, Status::Ok);
+        assert_eq!(body["data"]["attributes"]["message"], "video cat.mp4 3s");
+    }
+
+    #[test]
+    fn test_create_request_polymorphic_invalid_type() {
+        let (status, body) =
+            create(r#"{ "data": { "type": "sounds", "attributes": { "url": "cat.mp3" } } }"#);
+        assert_eq!(status, Status::Conflict);
+        assert_eq!(
+            body["errors"][0],
+            serde_json::json!({
+                "status": "409",
+                "detail": "invalid value: string \"sounds\", expected one of images, videos",
+                "source": { "pointer": "/data/type" }
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_request_polymorphic_invalid_attributes() {
+        let (status, _) =
+            create(r#"{ "data": { "type": "videos", "attributes": { "url": "cat.mp4" } } }"#);
+        assert_eq!(status, Status::Conflict);
+    }
+}
+
+mod test_request_media_type_params {
+    use crate::Test;
+    use rocket::http::{Header, Status};
+    use rocket::local::Client;
+    use rocket::Rocket;
+    use rocket_jsonapi::request::{
+        JsonApiCreateRequest, JsonApiRequest, MediaTypeSupport, NegotiatedMediaType,
+    };
+    use rocket_jsonapi::response::JsonApiDataResponse;
+
+    const EXTENSION: &str = "https://example.com/ext/supported";
+    const PROFILE: &str = "https://example.com/profiles/supported";
+
+    #[get("/simple")]
+    fn simple(_req: JsonApiRequest) -> JsonApiDataResponse<Test> {
+        JsonApiDataResponse(Ok(Test {
+            id: 1,
+            message: String::from("Hello!"),
+        }))
+    }
+
+    #[get("/profiles")]
+    fn profiles(media_type: NegotiatedMediaType) -> JsonApiDataResponse<Test> {
+        JsonApiDataResponse(Ok(Test {
+            id: 1,
+            message: media_type.profiles().join(" "),
+        }))
+    }
+
+    #[post("/simple_data", data = "<input>")]
+    fn simple_data(input: JsonApiCreateRequest<Test>) -> JsonApiDataResponse<Test> {
+        JsonApiDataResponse(Ok(input.0))
+    }
+
+    fn rocket() -> Rocket {
+        rocket::ignite()
+            .manage(
+                MediaTypeSupport::new()
+                    .extension(EXTENSION)
+                    .profile(PROFILE),
+            )
+            .mount("/", routes![simple, profiles, simple_data])
+    }
+
+    #[test]
+    fn test_request_accept_supported_extension() {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let mut request = client.get("/simple");
+        request.add_header(Header::new(
+            "Accept",
+            format!("application/vnd.api+json; ext=\"{}\"", EXTENSION),
+        ));
+        let response = request.dispatch();
+        // Test HTTP status code
+        assert_eq!(response.status(), Status::Ok);
+        // Test the applied extension is echoed
+        let headers = response.headers();
+        assert_eq!(
+            headers.get_one("Content-Type").unwrap(),
+            format!("application/vnd.api+json; ext=\"{}\"", EXTENSION)
+        );
+    }
+
+    #[test]
+    fn test_request_accept_unsupported_extension_406() {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let mut request = client.get("/simple");
+        request.add_header(Header::new(
+            "Accept",
+            "application/vnd.api+json; ext=\"https://example.com/ext/unsupported\"",
+        ));
+        let response = request.dispatch();
+        // Test HTTP status code
+        assert_eq!(response.status(), Status::NotAcceptable);
+    }
+
+    #[test]
+    fn test_request_accept_unsupported_extension_least_one_valid() {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let mut request = client.get("/simple");
+        request.add_header(Header::new(
+            "Accept",
+            "application/vnd.api+json; ext=\"https://example.com/ext/unsupported\", application/vnd.api+json",
+        ));
+        let response = request.dispatch();
+        // Test HTTP status code
+        assert_eq!(response.status(), Status::Ok);
+        // Test header response, no extension is applied
+        let headers = response.headers();
+        assert_eq!(
+            headers.get_one("Content-Type").unwrap(),
+            "application/vnd.api+json"
+        );
+    }
+
+    #[test]
+    fn test_request_accept_profiles() {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let mut request = client.get("/profiles");
+        request.add_header(Header::new(
+            "Accept",
+            format!(
+                "application/vnd.api+json; profile=\"{} https://example.com/profiles/unsupported\"",
+                PROFILE
+            ),
+        ));
+        let mut response = request.dispatch();
+        // Test HTTP status code
+        assert_eq!(response.status(), Status::Ok);
+        // Test only the supported profile is applied
+        assert_eq!(
+            response.headers().get_one("Content-Type").unwrap(),
+            format!("application/vnd.api+json; profile=\"{}\"", PROFILE)
+        );
+        assert!(response
+            .body_string()
+            .unwrap()
+            .contains(&format!("\"message\":\"{}\"", PROFILE)));
+    }
+
+    #[test]
+    fn test_create_request_content_type_supported_extension() {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let mut request = client.post("/simple_data");
+        request.add_header(Header::new(
+            "Content-Type",
+            format!("application/vnd.api+json; ext=\"{}\"", EXTENSION),
+        ));
+        request.add_header(Header::new("Accept", "application/vnd.api+json"));
+        request = request.body(
+            r#"
+        {
+            "data": {
+                "type": "Test",
+                "attributes": {
+                    "id": 1,
+                    "message": "Hay!"
+                }
+            }
+        }
+        "#,
+        );
+        let response = request.dispatch();
+        // Test HTTP status code
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    #[test]
+    fn test_create_request_content_type_unsupported_extension_415() {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let mut request = client.post("/simple_data");
+        request.add_header(Header::new(
+            "Content-Type",
+            "application/vnd.api+json; ext=\"https://example.com/ext/unsupported\"",
+        ));
+        request.add_header(Header::new("Accept", "application/vnd.api+json"));
+        request = request.body(
+            r#"
+        {
+            "data": {
+                "type": "Test",
+                "attributes": {
+                    "id": 1,
+                    "message": "Hay!"
+                }
+            }
+        }
+        "#,
+        );
+        let response = request.dispatch();
+        // Test HTTP status code
+        assert_eq!(response.status(), Status::UnsupportedMediaType);
+    }
+
+    #[test]
+    fn test_request_no_support_managed_extension_406() {
+        let rocket = rocket::ignite().mount("/", routes![simple]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let mut request = client.get("/simple");
+        request.add_header(Header::new(
+            "Accept",
+            format!("application/vnd.api+json; ext=\"{}\"", EXTENSION),
+        ));
+        let response = request.dispatch();
+        // Test HTTP status code
+        assert_eq!(response.status(), Status::NotAcceptable);
+    }
+}
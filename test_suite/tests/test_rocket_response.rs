@@ -44,6 +44,18 @@ mod test_output_data_response {
         ])))
     }
 
+    #[get("/simple_optional?<some>")]
+    fn simple_optional(some: bool) -> JsonApiDataResponse<Option<Test>> {
+        JsonApiDataResponse(Ok(if some {
+            Some(Test {
+                id: 1,
+                message: String::from("Hello!"),
+            })
+        } else {
+            None
+        }))
+    }
+
     #[get("/simple_error")]
     fn simple_error() -> JsonApiDataResponse<Test> {
         JsonApiDataResponse(Err(JsonApiResponseError::new(
@@ -55,6 +67,32 @@ mod test_output_data_response {
         )))
     }
 
+    #[test]
+    fn rocket_simple_none_response() {
+        let rocket = rocket::ignite().mount("/", routes![simple_optional]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let mut response = client.get("/simple_optional?some=false").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let requested_json: Value = from_str(response.body_string().unwrap().as_str()).unwrap();
+        let expected_json = json!({
+            "data": null,
+            "links": {
+                "self": "/simple_optional?some=false"
+            }
+        });
+        assert_eq!(requested_json, expected_json);
+    }
+
+    #[test]
+    fn rocket_simple_some_response() {
+        let rocket = rocket::ignite().mount("/", routes![simple_optional]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let mut response = client.get("/simple_optional?some=true").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let requested_json: Value = from_str(response.body_string().unwrap().as_str()).unwrap();
+        assert_eq!(requested_json["data"]["id"], "1");
+    }
+
     #[test]
     fn rocket_simple_ok_response() {
         let rocket = rocket::ignite().mount("/", routes![simple]);
@@ -78,6 +116,9 @@ mod test_output_data_response {
                     "id": 1,
                     "message": "Hello!"
                 }
+            },
+            "links": {
+                "self": "/simple"
             }
         });
         assert_eq!(requested_json, expected_json);
@@ -114,7 +155,10 @@ mod test_output_data_response {
                     "id": 2,
                     "message": "Hay!"
                 }
-            }]
+            }],
+            "links": {
+                "self": "/simple_list"
+            }
         });
         assert_eq!(requested_json.unwrap(), expected_json);
     }
@@ -157,6 +201,8 @@ mod test_output_data_response_links {
     struct TestWithLinks {
         id: u64,
         message: String,
+        #[serde(skip_serializing)]
+        link_meta: Meta,
     }
 
     #[derive(Serialize)]
@@ -167,18 +213,11 @@ mod test_output_data_response_links {
     impl Linkify for TestWithLinks {
         fn get_links(&self) -> Vec<Link> {
             vec![
-                Link::Url(
-                    "self",
-                    String::from("http://fake.com/api/test_with_links/1"),
-                ),
-                Link::Object(
+                Link::url("self", "http://fake.com/api/test_with_links/1"),
+                Link::object(
                     "something",
-                    LinkObject {
-                        href: String::from("http://fake.com/api/test_with_links/1/something"),
-                        meta: Box::new(Meta {
-                            stuff: String::from("stuff"),
-                        }),
-                    },
+                    LinkObject::new("http://fake.com/api/test_with_links/1/something")
+                        .meta(&self.link_meta),
                 ),
             ]
         }
@@ -189,19 +228,22 @@ mod test_output_data_response_links {
         JsonApiDataResponse(Ok(TestWithLinks {
             id: 1,
             message: String::from("Hello!"),
+            link_meta: Meta {
+                stuff: String::from("stuff"),
+            },
         }))
     }
 
     #[get("/simple_links")]
     fn simple_list() -> JsonApiDataResponse<JsonApiCollection<TestWithLinks>> {
         JsonApiDataResponse(Ok(JsonApiCollection::data_w_links(
-            vec![Link::Url(
-                "self",
-                String::from("http://fake.com/api/test_with_links"),
-            )],
+            vec![Link::url("self", "http://fake.com/api/test_with_links")],
             vec![TestWithLinks {
                 id: 1,
                 message: String::from("Hello!"),
+                link_meta: Meta {
+                    stuff: String::from("stuff"),
+                },
             }],
         )))
     }
@@ -275,12 +317,689 @@ mod test_output_data_response_links {
     }
 }
 
+mod test_output_data_response_meta {
+    use rocket::http::Status;
+    use rocket::local::Client;
+    use rocket_jsonapi::response::JsonApiDataResponse;
+    use rocket_jsonapi::JsonApiResource;
+    use serde::Serialize;
+    use serde_json::{from_str, json, Value};
+
+    #[derive(Serialize, JsonApiResource)]
+    #[jsonapi(type = "articles", meta(words = self.text.split_whitespace().count()))]
+    struct Article {
+        id: i32,
+        text: String,
+    }
+
+    // Has the meta of the article
+    #[derive(JsonApiResource)]
+    #[jsonapi(transparent)]
+    struct Published(Article);
+
+    // Replaces the meta of the article
+    #[derive(JsonApiResource)]
+    #[jsonapi(transparent, meta(draft = true))]
+    struct Draft(Article);
+
+    #[derive(Serialize, JsonApiResource)]
+    #[jsonapi(type = "notes")]
+    struct Note {
+        id: i32,
+    }
+
+    // Has the meta of the resource of the variant, if any
+    #[derive(JsonApiResource)]
+    enum Entry {
+        Article(Article),
+        Note(Note),
+    }
+
+    fn article() -> Article {
+        Article {
+            id: 1,
+            text: String::from("Hello world"),
+        }
+    }
+
+    #[get("/published")]
+    fn published() -> JsonApiDataResponse<Published> {
+        JsonApiDataResponse(Ok(Published(article())))
+    }
+
+    #[get("/draft")]
+    fn draft() -> JsonApiDataResponse<Draft> {
+        JsonApiDataResponse(Ok(Draft(article())))
+    }
+
+    #[get("/entries/<id>")]
+    fn entry(id: i32) -> JsonApiDataResponse<Entry> {
+        match id {
+            1 => JsonApiDataResponse(Ok(Entry::Article(article()))),
+            _ => JsonApiDataResponse(Ok(Entry::Note(Note { id }))),
+        }
+    }
+
+    fn resource_meta(path: &str) -> Value {
+        let rocket = rocket::ignite().mount("/", routes![published, draft, entry]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let mut response = client.get(path).dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let document: Value = from_str(response.body_string().unwrap().as_str()).unwrap();
+        document["data"]["meta"].clone()
+    }
+
+    #[test]
+    fn rocket_transparent_resource_meta() {
+        assert_eq!(resource_meta("/published"), json!({ "words": 2 }));
+        assert_eq!(resource_meta("/draft"), json!({ "draft": true }));
+    }
+
+    #[test]
+    fn rocket_polymorphic_resource_meta() {
+        assert_eq!(resource_meta("/entries/1"), json!({ "words": 2 }));
+        assert_eq!(resource_meta("/entries/2"), Value::Null);
+    }
+}
+
+mod test_output_data_response_link_context {
+    use rocket::http::{Header, Status};
+    use rocket::local::Client;
+    use rocket::Rocket;
+    use rocket_jsonapi::links::{Link, LinkConfig, LinkContext};
+    use rocket_jsonapi::response::JsonApiDataResponse;
+    use rocket_jsonapi::{Linkify, ResourceIdentifiable, ResourceType};
+    use serde::Serialize;
+    use serde_json::{from_str, json, Value};
+
+    #[derive(Serialize, ResourceType, ResourceIdentifiable)]
+    struct Article {
+        id: i32,
+    }
+
+    impl Linkify for Article {
+        fn get_links_in(&self, context: &LinkContext) -> Vec<Link> {
+            vec![
+                Link::url("self", context.uri(&uri!(article: self.id))),
+                Link::url("home", context.absolute(&uri!(home))),
+            ]
+        }
+    }
+
+    #[get("/articles/<id>")]
+    fn article(id: i32) -> JsonApiDataResponse<Article> {
+        JsonApiDataResponse(Ok(Article { id }))
+    }
+
+    #[get("/")]
+    fn home() {}
+
+    #[derive(Serialize, ResourceType, ResourceIdentifiable)]
+    struct HomeLinked {
+        id: i32,
+    }
+
+    impl Linkify for HomeLinked {
+        fn get_links_in(&self, context: &LinkContext) -> Vec<Link> {
+            vec![Link::url("home", context.absolute(&uri!(home)))]
+        }
+    }
+
+    #[get("/simple")]
+    fn simple() -> JsonApiDataResponse<HomeLinked> {
+        JsonApiDataResponse(Ok(HomeLinked { id: 1 }))
+    }
+
+    #[get("/context")]
+    fn context(context: LinkContext) -> String {
+        context.url("/path")
+    }
+
+    fn rocket() -> Rocket {
+        rocket::ignite()
+            .mount("/api", routes![article, simple, context])
+            .mount("/", routes![home])
+    }
+
+    fn links(client: &Client, headers: Vec<Header<'static>>) -> Value {
+        let mut request = client.get("/api/articles/1");
+        for header in headers {
+            request.add_header(header);
+        }
+        let mut response = request.dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let body: Value = from_str(response.body_string().unwrap().as_str()).unwrap();
+        body["links"].clone()
+    }
+
+    #[test]
+    fn test_links_from_host() {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let links = links(&client, vec![Header::new("Host", "example.com")]);
+        assert_eq!(links["self"], "http://example.com/api/articles/1");
+        assert_eq!(links["home"], "http://example.com/");
+    }
+
+    #[test]
+    fn test_links_from_forwarded() {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let links = links(
+            &client,
+            vec![
+                Header::new("Host", "internal:8000"),
+                Header::new("Forwarded", "for=192.0.2.60;proto=https;host=example.com"),
+            ],
+        );
+        assert_eq!(links["self"], "https://example.com/api/articles/1");
+    }
+
+    #[test]
+    fn test_links_from_config() {
+        let rocket = rocket().manage(LinkConfig::new().base_url("https://api.example.com/"));
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let links = links(&client, vec![Header::new("Host", "example.com")]);
+        assert_eq!(links["self"], "https://api.example.com/api/articles/1");
+    }
+
+    #[test]
+    fn test_links_relative_without_host() {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let links = links(&client, vec![]);
+        assert_eq!(links["self"], "/api/articles/1");
+    }
+
+    #[test]
+    fn test_self_link_from_request_uri() {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let mut request = client.get("/api/simple?sort=-id");
+        request.add_header(Header::new("Host", "example.com"));
+        let mut response = request.dispatch();
+        let body: Value = from_str(response.body_string().unwrap().as_str()).unwrap();
+        // The self link is merged with the links of the resource
+        assert_eq!(
+            body["links"],
+            json!({
+                "self": "http://example.com/api/simple?sort=-id",
+                "home": "http://example.com/"
+            })
+        );
+    }
+
+    #[test]
+    fn test_self_link_opt_out() {
+        let rocket = rocket().manage(LinkConfig::new().without_self_link());
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let mut response = client.get("/api/simple").dispatch();
+        let body: Value = from_str(response.body_string().unwrap().as_str()).unwrap();
+        assert_eq!(body["links"], json!({ "home": "/" }));
+    }
+
+    #[test]
+    fn test_link_context_guard() {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let mut request = client.get("/api/context");
+        request.add_header(Header::new("Host", "example.com"));
+        let mut response = request.dispatch();
+        assert_eq!(
+            response.body_string().unwrap(),
+            "http://example.com/api/path"
+        );
+    }
+}
+
+mod test_output_data_response_relationships {
+    use rocket::http::Status;
+    use rocket::local::Client;
+    use rocket_jsonapi::links::ResourceRoutes;
+    use rocket_jsonapi::relationship::{
+        HaveRelationship, RelationObject, RelationObjectify, Relationships,
+    };
+    use rocket_jsonapi::response::{JsonApiCollection, JsonApiDataResponse};
+    use rocket_jsonapi::{Linkify, ResourceIdentifiable, ResourceType};
+    use serde::Serialize;
+    use serde_json::{from_str, json, Value};
+
+    #[derive(Serialize, ResourceType, ResourceIdentifiable, Linkify)]
+    struct Person {
+        id: i32,
+    }
+
+    #[derive(Serialize, ResourceType, ResourceIdentifiable, Linkify)]
+    struct Article {
+        id: i32,
+        #[serde(skip_serializing)]
+        author: Option<i32>,
+        #[serde(skip_serializing)]
+        comments: Vec<i32>,
+    }
+
+    impl HaveRelationship<'_, Option<Person>> for Article {
+        fn get_relation(&self) -> Option<Person> {
+            self.author.map(|id| Person { id })
+        }
+    }
+
+    impl HaveRelationship<'_, JsonApiCollection<Person>> for Article {
+        fn get_relation(&self) -> JsonApiCollection<Person> {
+            JsonApiCollection::data(self.comments.iter().map(|&id| Person { id }).collect())
+        }
+    }
+
+    impl Relationships for Article {
+        fn get_all_relation_objects(&self) -> Vec<RelationObject> {
+            vec![
+                <Self as RelationObjectify<Option<Person>>>::get_relation_object(self, "author"),
+                <Self as RelationObjectify<JsonApiCollection<Person>>>::get_relation_object(
+                    self, "comments",
+                ),
+            ]
+        }
+    }
+
+    #[get("/articles")]
+    fn articles() -> JsonApiDataResponse<JsonApiCollection<Article>> {
+        JsonApiDataResponse(Ok(JsonApiCollection::data(vec![
+            Article {
+                id: 1,
+                author: Some(3),
+                comments: vec![4],
+            },
+            Article {
+                id: 2,
+                author: None,
+                comments: vec![],
+            },
+        ])))
+    }
+
+    #[test]
+    fn rocket_relationships_response() {
+        let rocket = rocket::ignite().mount("/", routes![articles]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let mut response = client.get("/articles").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let requested_json: Value = from_str(response.body_string().unwrap().as_str()).unwrap();
+        let expected_json = json!({
+            "data": [{
+                "id": "1",
+                "type": "Article",
+                "attributes": {
+                    "id": 1
+                },
+                "relationships": {
+                    "author": {
+                        "data": { "id": "3", "type": "Person" }
+                    },
+                    "comments": {
+                        "data": [{ "id": "4", "type": "Person" }]
+                    }
+                }
+            }, {
+                "id": "2",
+                "type": "Article",
+                "attributes": {
+                    "id": 2
+                },
+                "relationships": {
+                    "author": {
+                        "data": null
+                    },
+                    "comments": {
+                        "data": []
+                    }
+                }
+            }],
+            "links": {
+                "self": "/articles"
+            }
+        });
+        assert_eq!(requested_json, expected_json);
+    }
+
+    #[test]
+    fn rocket_relationships_response_resource_links() {
+        let rocket = rocket::ignite()
+            .manage(ResourceRoutes::new().resource::<Article>("/articles"))
+            .mount("/", routes![articles]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let mut response = client.get("/articles").dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let requested_json: Value = from_str(response.body_string().unwrap().as_str()).unwrap();
+        assert_eq!(
+            requested_json["data"][0]["relationships"],
+            json!({
+                "author": {
+                    "data": { "id": "3", "type": "Person" },
+                    "links": {
+                        "self": "/articles/1/relationships/author",
+                        "related": "/articles/1/author"
+                    }
+                },
+                "comments": {
+                    "data": [{ "id": "4", "type": "Person" }],
+                    "links": {
+                        "self": "/articles/1/relationships/comments",
+                        "related": "/articles/1/comments"
+                    }
+                }
+            })
+        );
+    }
+}
+
+mod test_related_response {
+    use rocket::http::Status;
+    use rocket::local::Client;
+    use rocket::Rocket;
+    use rocket_jsonapi::error::JsonApiResponseError;
+    use rocket_jsonapi::links::{LinkConfig, Pagination, ResourceRoutes};
+    use rocket_jsonapi::relationship::HaveRelationship;
+    use rocket_jsonapi::response::{
+        JsonApiCollection, JsonApiDataResponse, JsonApiRelatedResponse, JsonApiRelationshipResponse,
+    };
+    use rocket_jsonapi::{Linkify, ResourceIdentifiable, ResourceType};
+    use serde::Serialize;
+    use serde_json::{from_str, json, Value};
+
+    #[derive(Serialize, ResourceType, ResourceIdentifiable, Linkify)]
+    struct Person {
+        id: i32,
+    }
+
+    #[derive(Serialize, ResourceType, ResourceIdentifiable, Linkify)]
+    struct Article {
+        id: i32,
+        #[serde(skip_serializing)]
+        author: Option<i32>,
+        #[serde(skip_serializing)]
+        comments: Vec<i32>,
+    }
+
+    impl HaveRelationship<'_, Option<Person>> for Article {
+        fn get_relation(&self) -> Option<Person> {
+            self.author.map(|id| Person { id })
+        }
+    }
+
+    impl HaveRelationship<'_, JsonApiCollection<Person>> for Article {
+        fn get_relation(&self) -> JsonApiCollection<Person> {
+            JsonApiCollection::data(self.comments.iter().map(|&id| Person { id }).collect())
+        }
+    }
+
+    fn article(id: i32) -> Result<Article, JsonApiResponseError> {
+        match id {
+            1 => Ok(Article {
+                id,
+                author: Some(3),
+                comments: vec![4, 5],
+            }),
+            2 => Ok(Article {
+                id,
+                author: None,
+                comments: vec![],
+            }),
+            _ => Err(JsonApiResponseError::new(Status::NotFound, vec![])),
+        }
+    }
+
+    #[get("/articles/<id>/author")]
+    fn author(id: i32) -> JsonApiRelatedResponse<Article, Option<Person>> {
+        JsonApiRelatedResponse::new("author", article(id))
+    }
+
+    #[get("/articles/<id>/comments")]
+    fn comments(id: i32) -> JsonApiRelatedResponse<Article, JsonApiCollection<Person>> {
+        JsonApiRelatedResponse::new("comments", article(id))
+    }
+
+    #[derive(Serialize)]
+    struct PageMeta {
+        total: usize,
+    }
+
+    // Article 3 has so many comments they are paginated
+    #[derive(ResourceType, ResourceIdentifiable, Linkify)]
+    #[resource_ident_type = "Article"]
+    struct PagedArticle {
+        id: i32,
+        page: usize,
+    }
+
+    impl HaveRelationship<'_, JsonApiCollection<Person, Pagination>> for PagedArticle {
+        fn get_relation(&self) -> JsonApiCollection<Person, Pagination> {
+            let uri = |page| format!("/articles/3/comments?page={}", page);
+            JsonApiCollection::paginated(
+                vec![Person {
+                    id: self.page as i32,
+                }],
+                Pagination::new()
+                    .first(uri(1))
+                    .prev(uri(self.page - 1))
+                    .next(uri(self.page + 1))
+                    .meta(PageMeta { total: 100 }),
+            )
+        }
+    }
+
+    #[get("/articles/3/comments?<page>")]
+    fn paged_comments(page: usize) -> JsonApiDataResponse<JsonApiCollection<Person, Pagination>> {
+        JsonApiDataResponse(Ok(PagedArticle { id: 3, page }.get_relation()))
+    }
+
+    #[get("/articles/<id>/relationships/comments", rank = 2)]
+    fn comments_relationship(
+        id: i32,
+    ) -> JsonApiRelationshipResponse<Article, JsonApiCollection<Person>> {
+        JsonApiRelationshipResponse::new("comments", article(id))
+    }
+
+    #[get("/articles/3/relationships/comments?<page>")]
+    fn paged_comments_relationship(
+        page: usize,
+    ) -> JsonApiRelationshipResponse<PagedArticle, JsonApiCollection<Person, Pagination>> {
+        JsonApiRelationshipResponse::new("comments", Ok(PagedArticle { id: 3, page }))
+    }
+
+    fn rocket() -> Rocket {
+        rocket::ignite()
+            .manage(ResourceRoutes::new().resource::<Article>("/articles"))
+            .mount(
+                "/",
+                routes![
+                    author,
+                    comments,
+                    paged_comments,
+                    comments_relationship,
+                    paged_comments_relationship
+                ],
+            )
+    }
+
+    fn get(rocket: Rocket, uri: &str) -> (Status, Value) {
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let mut response = client.get(uri).dispatch();
+        let body = from_str(response.body_string().unwrap().as_str()).unwrap();
+        (response.status(), body)
+    }
+
+    #[test]
+    fn rocket_related_to_one() {
+        let (status, body) = get(rocket(), "/articles/1/author");
+        assert_eq!(status, Status::Ok);
+        assert_eq!(
+            body,
+            json!({
+                "data": {
+                    "id": "3",
+                    "type": "Person",
+                    "attributes": { "id": 3 }
+                },
+                "links": {
+                    "self": "/articles/1/author",
+                    "related": "/articles/1"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn rocket_related_to_one_empty() {
+        let (status, body) = get(rocket(), "/articles/2/author");
+        assert_eq!(status, Status::Ok);
+        assert_eq!(body["data"], Value::Null);
+        assert_eq!(body["links"]["self"], "/articles/2/author");
+    }
+
+    #[test]
+    fn rocket_related_to_many() {
+        let (status, body) = get(rocket(), "/articles/1/comments");
+        assert_eq!(status, Status::Ok);
+        assert_eq!(
+            body["data"],
+            json!([{
+                "id": "4",
+                "type": "Person",
+                "attributes": { "id": 4 }
+            }, {
+                "id": "5",
+                "type": "Person",
+                "attributes": { "id": 5 }
+            }])
+        );
+        assert_eq!(
+            body["links"],
+            json!({
+                "self": "/articles/1/comments",
+                "related": "/articles/1"
+            })
+        );
+    }
+
+    #[test]
+    fn rocket_related_to_many_empty() {
+        let (status, body) = get(rocket(), "/articles/2/comments");
+        assert_eq!(status, Status::Ok);
+        assert_eq!(body["data"], json!([]));
+    }
+
+    #[test]
+    fn rocket_related_unknown_parent_url() {
+        let rocket = rocket::ignite().mount("/", routes![author]);
+        let (status, body) = get(rocket, "/articles/1/author");
+        assert_eq!(status, Status::Ok);
+        // Without the URL of the parent, the request URI is the self link
+        assert_eq!(body["links"], json!({ "self": "/articles/1/author" }));
+    }
+
+    #[test]
+    fn rocket_related_parent_error() {
+        let (status, body) = get(rocket(), "/articles/7/author");
+        assert_eq!(status, Status::NotFound);
+        assert_eq!(body, json!({ "errors": [] }));
+    }
+
+    #[test]
+    fn rocket_related_paginated() {
+        let (status, body) = get(rocket(), "/articles/3/comments?page=2");
+        assert_eq!(status, Status::Ok);
+        assert_eq!(
+            body,
+            json!({
+                "data": [{
+                    "id": "2",
+                    "type": "Person",
+                    "attributes": { "id": 2 }
+                }],
+                "links": {
+                    "self": "/articles/3/comments?page=2",
+                    "first": "/articles/3/comments?page=1",
+                    "prev": "/articles/3/comments?page=1",
+                    "next": "/articles/3/comments?page=3"
+                },
+                "meta": { "total": 100 }
+            })
+        );
+    }
+
+    #[test]
+    fn rocket_relationship_to_many() {
+        let (status, body) = get(rocket(), "/articles/1/relationships/comments");
+        assert_eq!(status, Status::Ok);
+        assert_eq!(
+            body,
+            json!({
+                "data": [
+                    { "id": "4", "type": "Person" },
+                    { "id": "5", "type": "Person" }
+                ],
+                "links": {
+                    "self": "/articles/1/relationships/comments",
+                    "related": "/articles/1/comments"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn rocket_relationship_paginated() {
+        let (status, body) = get(rocket(), "/articles/3/relationships/comments?page=2");
+        assert_eq!(status, Status::Ok);
+        assert_eq!(
+            body,
+            json!({
+                "data": [{ "id": "2", "type": "Person" }],
+                "links": {
+                    "self": "/articles/3/relationships/comments?page=2",
+                    "related": "/articles/3/comments",
+                    "first": "/articles/3/comments?page=1",
+                    "prev": "/articles/3/comments?page=1",
+                    "next": "/articles/3/comments?page=3"
+                },
+                "meta": { "total": 100 }
+            })
+        );
+    }
+
+    #[test]
+    fn rocket_relationship_unknown_parent_url() {
+        // Without `ResourceRoutes` only the request URI is known
+        let rocket = rocket::ignite().mount("/", routes![paged_comments_relationship]);
+        let (_, body) = get(rocket, "/articles/3/relationships/comments?page=2");
+        assert_eq!(
+            body["links"],
+            json!({
+                "self": "/articles/3/relationships/comments?page=2",
+                "first": "/articles/3/comments?page=1",
+                "prev": "/articles/3/comments?page=1",
+                "next": "/articles/3/comments?page=3"
+            })
+        );
+    }
+
+    #[test]
+    fn rocket_relationship_without_self_link() {
+        let rocket = rocket().manage(LinkConfig::new().without_self_link());
+        let (_, body) = get(rocket, "/articles/1/relationships/comments");
+        assert_eq!(body["links"], json!({ "related": "/articles/1/comments" }));
+    }
+
+    #[test]
+    fn rocket_relationship_parent_error() {
+        let (status, body) = get(rocket(), "/articles/7/relationships/comments");
+        assert_eq!(status, Status::NotFound);
+        assert_eq!(body, json!({ "errors": [] }));
+    }
+}
+
 mod test_create_response {
     use crate::Test;
-    use rocket::http::Status;
+    use rocket::http::{Header, Status};
     use rocket::local::Client;
     use rocket::request::FromFormValue;
+    use rocket_jsonapi::links::ResourceRoutes;
     use rocket_jsonapi::response::JsonApiCreateResponse;
+    use rocket_jsonapi::{Linkify, ResourceIdentifiable, ResourceType};
     use serde::{Deserialize, Serialize};
     use serde_json::{from_str, json, Value};
 
@@ -296,6 +1015,29 @@ mod test_create_response {
         Other,
     }
 
+    #[derive(Serialize, ResourceType, ResourceIdentifiable, Linkify)]
+    #[links(self = "/tests_with_self/{id}")]
+    struct TestWithSelf {
+        id: i32,
+    }
+
+    #[get("/created_with_self")]
+    fn created_with_self() -> JsonApiCreateResponse<TestWithSelf> {
+        JsonApiCreateResponse::Created(TestWithSelf { id: 5 })
+    }
+
+    #[derive(Serialize, ResourceType, ResourceIdentifiable, Linkify)]
+    struct Slug {
+        id: String,
+    }
+
+    #[get("/created_slug")]
+    fn created_slug() -> JsonApiCreateResponse<Slug> {
+        JsonApiCreateResponse::Created(Slug {
+            id: String::from("hello world/2?"),
+        })
+    }
+
     #[get("/simple?<trigger>")]
     fn simple(trigger: CreateResponseTrigger) -> JsonApiCreateResponse<Test> {
         let test = Test {
@@ -341,11 +1083,70 @@ mod test_create_response {
                     "id": 5,
                     "message": "Bob"
                 }
+            },
+            "links": {
+                "self": "/simple?trigger=Created"
             }
         });
         assert_eq!(requested_json, expected_json);
     }
 
+    #[test]
+    fn rocket_create_response_created_location_from_routes() {
+        let rocket = rocket::ignite()
+            .manage(ResourceRoutes::new().resource::<Test>("/api/tests/"))
+            .mount("/", routes![simple]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let mut request = client.get("/simple?trigger=Created");
+        request.add_header(Header::new("Host", "example.com"));
+        let response = request.dispatch();
+        assert_eq!(response.status(), Status::Created);
+        assert_eq!(
+            response.headers().get_one("Location").unwrap(),
+            "http://example.com/api/tests/5"
+        );
+    }
+
+    #[test]
+    fn rocket_create_response_created_location_from_links() {
+        let rocket = rocket::ignite()
+            .manage(ResourceRoutes::new().resource::<TestWithSelf>("/api/tests"))
+            .mount("/", routes![created_with_self]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let response = client.get("/created_with_self").dispatch();
+        assert_eq!(response.status(), Status::Created);
+        // The self link of the resource takes precedence over the registered route
+        assert_eq!(
+            response.headers().get_one("Location").unwrap(),
+            "/tests_with_self/5"
+        );
+    }
+
+    #[test]
+    fn rocket_create_response_created_location_encodes_id() {
+        let rocket = rocket::ignite()
+            .manage(ResourceRoutes::new().resource::<Slug>("/slugs"))
+            .mount("/", routes![created_slug]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let response = client.get("/created_slug").dispatch();
+        assert_eq!(response.status(), Status::Created);
+        assert_eq!(
+            response.headers().get_one("Location").unwrap(),
+            "/slugs/hello%20world%2F2%3F"
+        );
+    }
+
+    #[test]
+    fn rocket_create_response_accepted_no_location() {
+        let rocket = rocket::ignite()
+            .manage(ResourceRoutes::new().resource::<Test>("/api/tests"))
+            .mount("/", routes![simple]);
+        let client = Client::new(rocket).expect("valid rocket instance");
+        let response = client.get("/simple?trigger=Accepted").dispatch();
+        assert_eq!(response.status(), Status::Accepted);
+        assert_eq!(response.headers().get_one("Location"), None);
+    }
+
     #[test]
     fn rocket_create_response_accepted() {
         let rocket = rocket::ignite().mount("/", routes![simple]);
@@ -369,6 +1170,9 @@ mod test_create_response {
                     "id": 5,
                     "message": "Bob"
                 }
+            },
+            "links": {
+                "self": "/simple?trigger=Accepted"
             }
         });
         assert_eq!(requested_json, expected_json);
@@ -558,6 +1362,9 @@ mod test_header_update_response {
                     "id": 5,
                     "message": "Bob"
                 }
+            },
+            "links": {
+                "self": "/simple?trigger=Updated"
             }
         });
         assert_eq!(requested_json, expected_json);
@@ -586,6 +1393,9 @@ mod test_header_update_response {
                     "id": 5,
                     "message": "Bob"
                 }
+            },
+            "links": {
+                "self": "/simple?trigger=Accepted"
             }
         });
         assert_eq!(requested_json, expected_json);
@@ -691,3 +1501,268 @@ mod test_header_update_response {
         assert_eq!(requested_json, expected_json);
     }
 }
+
+mod test_polymorphic_response {
+    use rocket::local::Client;
+    use rocket::Rocket;
+    use rocket_jsonapi::response::{JsonApiCollection, JsonApiDataResponse};
+    use rocket_jsonapi::JsonApiResource;
+    use serde::Serialize;
+    use serde_json::{from_str, json, Value};
+
+    #[derive(Serialize, JsonApiResource)]
+    #[jsonapi(type = "articles", links(self = "/articles/{id}"))]
+    struct Article {
+        id: i32,
+        title: String,
+    }
+
+    #[derive(Serialize, JsonApiResource)]
+    #[jsonapi(type = "videos")]
+    struct Video {
+        id: i32,
+        #[serde(skip_serializing)]
+        #[jsonapi(relationship)]
+        uploader: Person,
+    }
+
+    #[derive(Serialize, JsonApiResource)]
+    #[jsonapi(type = "people")]
+    struct Person {
+        id: i32,
+    }
+
+    #[derive(JsonApiResource)]
+    enum SearchResult {
+        Article(Article),
+        Video(Video),
+    }
+
+    #[derive(JsonApiResource)]
+    enum Commentable {
+        Article(Article),
+        Video { video: Video },
+    }
+
+    #[derive(Serialize, JsonApiResource)]
+    #[jsonapi(type = "comments", links(self = "/comments/{id}"))]
+    struct Comment {
+        id: i32,
+        #[serde(skip_serializing)]
+        #[jsonapi(relationship)]
+        commentable: Commentable,
+    }
+
+    #[get("/search")]
+    fn search() -> JsonApiDataResponse<JsonApiCollection<SearchResult>> {
+        JsonApiDataResponse(Ok(JsonApiCollection::data(vec![
+            SearchResult::Article(Article {
+                id: 1,
+                title: "Polymorphism".to_string(),
+            }),
+            SearchResult::Video(Video {
+                id: 2,
+                uploader: Person { id: 3 },
+            }),
+        ])))
+    }
+
+    #[get("/comments")]
+    fn comments() -> JsonApiDataResponse<JsonApiCollection<Comment>> {
+        JsonApiDataResponse(Ok(JsonApiCollection::data(vec![
+            Comment {
+                id: 4,
+                commentable: Commentable::Article(Article {
+                    id: 1,
+                    title: "Polymorphism".to_string(),
+                }),
+            },
+            Comment {
+                id: 5,
+                commentable: Commentable::Video {
+                    video: Video {
+                        id: 2,
+                        uploader: Person { id: 3 },
+                    },
+                },
+            },
+        ])))
+    }
+
+    fn rocket() -> Rocket {
+        rocket::ignite().mount("/", routes![search, comments])
+    }
+
+    fn get(uri: &str) -> Value {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let mut response = client.get(uri).dispatch();
+        from_str(response.body_string().unwrap().as_str()).unwrap()
+    }
+
+    #[test]
+    fn rocket_polymorphic_collection() {
+        assert_eq!(
+            get("/search"),
+            json!({
+                "data": [{
+                    "id": "1",
+                    "type": "articles",
+                    "attributes": { "id": 1, "title": "Polymorphism" }
+                }, {
+                    "id": "2",
+                    "type": "videos",
+                    "attributes": { "id": 2 },
+                    "relationships": {
+                        "uploader": { "data": { "id": "3", "type": "people" } }
+                    }
+                }],
+                "links": { "self": "/search" }
+            })
+        );
+    }
+
+    #[test]
+    fn rocket_polymorphic_relationship() {
+        assert_eq!(
+            get("/comments"),
+            json!({
+                "data": [{
+                    "id": "4",
+                    "type": "comments",
+                    "attributes": { "id": 4 },
+                    "relationships": {
+                        "commentable": {
+                            "data": { "id": "1", "type": "articles" },
+                            "links": {
+                                "self": "/comments/4/relationships/commentable",
+                                "related": "/comments/4/commentable"
+                            }
+                        }
+                    }
+                }, {
+                    "id": "5",
+                    "type": "comments",
+                    "attributes": { "id": 5 },
+                    "relationships": {
+                        "commentable": {
+                            "data": { "id": "2", "type": "videos" },
+                            "links": {
+                                "self": "/comments/5/relationships/commentable",
+                                "related": "/comments/5/commentable"
+                            }
+                        }
+                    }
+                }],
+                "links": { "self": "/comments" }
+            })
+        );
+    }
+}
+
+mod test_shared_response {
+    use rocket::local::Client;
+    use rocket::{Rocket, State};
+    use rocket_jsonapi::response::{JsonApiCollection, JsonApiDataResponse};
+    use rocket_jsonapi::JsonApiResource;
+    use serde::Serialize;
+    use serde_json::{from_str, json, Value};
+    use std::borrow::Cow;
+    use std::sync::Arc;
+
+    #[derive(Clone, Serialize, JsonApiResource)]
+    #[jsonapi(type = "articles", links(self = "/articles/{id}"))]
+    struct Article {
+        id: i32,
+        #[serde(skip_serializing)]
+        #[jsonapi(relationship)]
+        author: Person,
+    }
+
+    #[derive(Clone, Serialize, JsonApiResource)]
+    #[jsonapi(type = "people")]
+    struct Person {
+        id: i32,
+    }
+
+    // Resources held by a cache, served without cloning them
+    struct Cache(Vec<Arc<Article>>);
+
+    #[get("/articles/<id>")]
+    fn article(cache: State<Cache>, id: i32) -> JsonApiDataResponse<Arc<Article>> {
+        let article = cache.0.iter().find(|article| article.id == id).unwrap();
+        JsonApiDataResponse(Ok(Arc::clone(article)))
+    }
+
+    #[get("/articles")]
+    fn articles(cache: State<Cache>) -> JsonApiDataResponse<JsonApiCollection<&Article>> {
+        let articles = cache.inner().0.iter().map(|article| &**article).collect();
+        JsonApiDataResponse(Ok(JsonApiCollection::data(articles)))
+    }
+
+    #[get("/borrowed")]
+    fn borrowed(cache: State<Cache>) -> JsonApiDataResponse<JsonApiCollection<Cow<Article>>> {
+        let articles = cache
+            .inner()
+            .0
+            .iter()
+            .map(|article| Cow::Borrowed(&**article));
+        JsonApiDataResponse(Ok(JsonApiCollection::data(articles.collect())))
+    }
+
+    fn rocket() -> Rocket {
+        let cache = Cache(vec![
+            Arc::new(Article {
+                id: 1,
+                author: Person { id: 3 },
+            }),
+            Arc::new(Article {
+                id: 2,
+                author: Person { id: 4 },
+            }),
+        ]);
+        rocket::ignite()
+            .manage(cache)
+            .mount("/", routes![article, articles, borrowed])
+    }
+
+    fn get(uri: &str) -> Value {
+        let client = Client::new(rocket()).expect("valid rocket instance");
+        let mut response = client.get(uri).dispatch();
+        from_str(response.body_string().unwrap().as_str()).unwrap()
+    }
+
+    fn article_json(id: i32, author_id: i32) -> Value {
+        json!({
+            "id": id.to_string(),
+            "type": "articles",
+            "attributes": { "id": id },
+            "relationships": {
+                "author": {
+                    "data": { "id": author_id.to_string(), "type": "people" },
+                    "links": {
+                        "self": format!("/articles/{}/relationships/author", id),
+                        "related": format!("/articles/{}/author", id)
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn rocket_arc_resource() {
+        assert_eq!(
+            get("/articles/2"),
+            json!({
+                "data": article_json(2, 4),
+                "links": { "self": "/articles/2" }
+            })
+        );
+    }
+
+    #[test]
+    fn rocket_borrowed_collections() {
+        let expected = vec![article_json(1, 3), article_json(2, 4)];
+        assert_eq!(get("/articles")["data"], json!(expected));
+        assert_eq!(get("/borrowed")["data"], json!(expected));
+    }
+}
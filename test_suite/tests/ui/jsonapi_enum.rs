@@ -0,0 +1,20 @@
+use rocket_jsonapi::JsonApiResource;
+
+#[derive(JsonApiResource)]
+struct Article {
+    id: i32,
+}
+
+#[derive(JsonApiResource)]
+#[jsonapi(links(self = "/search/{id}"))]
+enum SearchResult {
+    Article(Article),
+}
+
+#[derive(JsonApiResource)]
+enum Feed {
+    Article(Article),
+    Pair(Article, Article),
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+use rocket_jsonapi::JsonApiResource;
+use serde::Serialize;
+
+#[derive(Serialize, JsonApiResource)]
+struct Article {
+    id: i32,
+}
+
+#[derive(Serialize, JsonApiResource)]
+struct Tag {
+    id: String,
+}
+
+#[derive(JsonApiResource)]
+enum SearchResult {
+    Article(Article),
+    Tag(Tag),
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use rocket_jsonapi::JsonApiResource;
+
+#[derive(JsonApiResource)]
+#[jsonapi(relationship(name = "author"))]
+struct Article {
+    id: i32,
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use rocket_jsonapi::JsonApiResource;
+
+#[derive(JsonApiResource)]
+struct Person {
+    id: i32,
+}
+
+#[derive(JsonApiResource)]
+#[jsonapi(transparent)]
+struct Author(Person, String);
+
+#[derive(JsonApiResource)]
+#[jsonapi(transparent, id = "name")]
+struct Reviewer(Person);
+
+fn main() {}
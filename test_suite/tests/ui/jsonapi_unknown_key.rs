@@ -0,0 +1,9 @@
+use rocket_jsonapi::JsonApiResource;
+
+#[derive(JsonApiResource)]
+#[jsonapi(type = "articles", kind = "articles")]
+struct Article {
+    id: i32,
+}
+
+fn main() {}
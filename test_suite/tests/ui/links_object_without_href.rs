@@ -0,0 +1,9 @@
+use rocket_jsonapi::Linkify;
+
+#[derive(Linkify)]
+#[links(describedby(title = "Schema"))]
+struct Article {
+    title: String,
+}
+
+fn main() {}
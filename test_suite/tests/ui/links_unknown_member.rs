@@ -0,0 +1,9 @@
+use rocket_jsonapi::Linkify;
+
+#[derive(Linkify)]
+#[links(self(href = "/articles", colour = "red"))]
+struct Article {
+    title: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use rocket_jsonapi::Relationships;
+
+#[derive(Relationships)]
+struct Article {
+    #[jsonapi(relation)]
+    author: String,
+}
+
+fn main() {}
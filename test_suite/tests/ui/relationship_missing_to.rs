@@ -0,0 +1,9 @@
+use rocket_jsonapi::Relationships;
+
+#[derive(Relationships)]
+#[relationship(name = "author")]
+struct Article {
+    id: i32,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use rocket_jsonapi::Relationships;
+
+#[derive(Relationships)]
+#[relationship(name = "author", too = Person)]
+struct Article {
+    id: i32,
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+use rocket_jsonapi::ResourceId;
+
+#[derive(ResourceId)]
+#[resource_id(separator = "")]
+struct PostKey {
+    author_id: i32,
+    slug: String,
+}
+
+#[derive(ResourceId)]
+#[resource_id(separator = "x0")]
+struct VersionKey(u32, u32);
+
+#[derive(ResourceId)]
+#[resource_id(separator = "%")]
+struct PercentKey(u32, u32);
+
+#[derive(ResourceId)]
+#[resource_id(delimiter = ":")]
+struct CommentKey(i32, i32);
+
+fn main() {}
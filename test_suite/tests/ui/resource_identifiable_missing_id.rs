@@ -0,0 +1,14 @@
+use rocket_jsonapi::{ResourceIdentifiable, ResourceType};
+
+#[derive(ResourceType, ResourceIdentifiable)]
+struct Article {
+    key: i32,
+}
+
+#[derive(ResourceType, ResourceIdentifiable)]
+#[resource_ident_id = "slug"]
+struct Comment {
+    id: i32,
+}
+
+fn main() {}
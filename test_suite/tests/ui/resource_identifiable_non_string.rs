@@ -0,0 +1,9 @@
+use rocket_jsonapi::{ResourceIdentifiable, ResourceType};
+
+#[derive(ResourceType, ResourceIdentifiable)]
+#[resource_ident_id = true]
+struct Article {
+    id: i32,
+}
+
+fn main() {}
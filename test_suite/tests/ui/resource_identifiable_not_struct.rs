@@ -0,0 +1,11 @@
+use rocket_jsonapi::{ResourceIdentifiable, ResourceType};
+
+#[derive(ResourceType, ResourceIdentifiable)]
+enum Article {
+    Draft,
+}
+
+#[derive(ResourceType, ResourceIdentifiable)]
+struct Comment(i32);
+
+fn main() {}
@@ -0,0 +1,9 @@
+use rocket_jsonapi::ResourceType;
+
+#[derive(ResourceType)]
+#[resource_ident_type(name = "articles")]
+struct Article {
+    id: i32,
+}
+
+fn main() {}
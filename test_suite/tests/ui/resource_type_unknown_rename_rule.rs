@@ -0,0 +1,9 @@
+use rocket_jsonapi::ResourceType;
+
+#[derive(ResourceType)]
+#[resource_ident_type(rename_all = "SCREAMING_SNAKE_CASE")]
+struct Article {
+    id: i32,
+}
+
+fn main() {}